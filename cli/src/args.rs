@@ -0,0 +1,88 @@
+use spectrogram::{
+    additive::{OscillatorBank, SynthesisEngine},
+    noise::{NoiseDistribution, RandomPhases},
+};
+
+// Removes `flag` from `args`, returning whether it was present.
+pub fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    if let Some(pos) = args.iter().position(|a| a == flag) {
+        args.remove(pos);
+        true
+    } else {
+        false
+    }
+}
+
+// Removes `name` and the value following it from `args`.
+pub fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let pos = args.iter().position(|a| a == name)?;
+    if pos + 1 >= args.len() {
+        usage_error(&format!("{} expects a value", name));
+    }
+    args.remove(pos);
+    Some(args.remove(pos))
+}
+
+pub fn take_parsed_option<T: std::str::FromStr>(args: &mut Vec<String>, name: &str) -> Option<T> {
+    take_option(args, name).map(|v| {
+        v.parse()
+            .unwrap_or_else(|_| usage_error(&format!("invalid value for {}: {}", name, v)))
+    })
+}
+
+// `--sample-rate N`, which must be positive.
+pub fn take_sample_rate(args: &mut Vec<String>) -> Option<u32> {
+    let rate = take_parsed_option(args, "--sample-rate");
+    if rate == Some(0) {
+        usage_error("--sample-rate must be positive");
+    }
+    rate
+}
+
+// `--window N`, which must be even and positive, or `default`.
+pub fn take_window(args: &mut Vec<String>, default: usize) -> usize {
+    let window = take_parsed_option(args, "--window").unwrap_or(default);
+    if window == 0 || window % 2 == 1 {
+        usage_error("--window must be even and positive");
+    }
+    window
+}
+
+// `--engine fft`, `--engine bins` or `--engine peaks[:MAX]`, defaulting to inverse FFT with
+// `awful_hack` as the caller would otherwise pass it.
+pub fn take_engine(args: &mut Vec<String>, awful_hack: bool) -> SynthesisEngine {
+    let Some(engine) = take_option(args, "--engine") else {
+        return SynthesisEngine::InverseFft { awful_hack };
+    };
+    let bank = match engine.split_once(':') {
+        _ if engine == "fft" => return SynthesisEngine::InverseFft { awful_hack },
+        _ if engine == "bins" => OscillatorBank::EveryBin,
+        _ if engine == "peaks" => OscillatorBank::Peaks { max_count: 64 },
+        Some(("peaks", max)) => OscillatorBank::Peaks {
+            max_count: max
+                .parse()
+                .unwrap_or_else(|_| usage_error(&format!("invalid peak count: {}", max))),
+        },
+        _ => usage_error(&format!("unknown engine: {}", engine)),
+    };
+    SynthesisEngine::Additive(bank)
+}
+
+// `--noise uniform|gaussian|pink` and `--seed N`.
+pub fn take_noise(args: &mut Vec<String>) -> RandomPhases {
+    let distribution = match take_option(args, "--noise").as_deref() {
+        None | Some("uniform") => NoiseDistribution::UniformPhase,
+        Some("gaussian") => NoiseDistribution::GaussianComplex,
+        Some("pink") => NoiseDistribution::Pink,
+        Some(other) => usage_error(&format!("unknown noise: {}", other)),
+    };
+    RandomPhases {
+        distribution,
+        seed: take_parsed_option(args, "--seed"),
+    }
+}
+
+pub fn usage_error(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    std::process::exit(1)
+}
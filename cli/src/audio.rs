@@ -0,0 +1,28 @@
+use std::fs::File;
+
+use rodio::{Source, buffer::SamplesBuffer};
+use spectrogram::resample::resample;
+
+// Decodes a file and keeps only its first channel, resampled to `sample_rate` if given.
+pub fn load_mono(path: &str, sample_rate: Option<u32>) -> (Vec<f32>, u32) {
+    let fs = File::open(path).unwrap();
+    let audio = rodio::Decoder::try_from(fs).unwrap();
+    let channels = audio.channels();
+    let sr = audio.sample_rate();
+    let samples: Vec<_> = audio.step_by(channels as usize).collect();
+    match sample_rate {
+        Some(rate) => (resample(&samples, sr, rate), rate),
+        None => (samples, sr),
+    }
+}
+
+pub fn save_wav(path: &str, sample_rate: u32, samples: Vec<f32>) {
+    let mut buffer = SamplesBuffer::new(1, sample_rate, samples);
+    rodio::output_to_wav(&mut buffer, path).unwrap();
+}
+
+pub fn pad_to_same_length(a: &mut Vec<f32>, b: &mut Vec<f32>) {
+    let len = a.len().max(b.len());
+    a.resize(len, 0f32);
+    b.resize(len, 0f32);
+}
@@ -0,0 +1,74 @@
+use spectrogram::{
+    SpectrogramSettings,
+    ops::{CombineDomain, CombineOp},
+};
+
+use crate::{
+    args::{take_engine, take_flag, take_sample_rate, take_window, usage_error},
+    audio::{load_mono, pad_to_same_length, save_wav},
+};
+
+const USAGE: &str = "usage: cli combine <add|subtract|multiply|mix:W|max|min|crossfade:START:END> \
+[--magnitude] [--window N] [--sample-rate N] [--engine fft|bins|peaks[:N]] <a> <b> <out.wav>";
+
+enum Operation {
+    Combine(CombineOp),
+    // Start and end of the fade, in seconds.
+    Crossfade(f32, f32),
+}
+
+fn parse_operation(s: &str) -> Option<Operation> {
+    let mut parts = s.split(':');
+    let op = match parts.next()? {
+        "add" => Operation::Combine(CombineOp::Add),
+        "subtract" => Operation::Combine(CombineOp::Subtract),
+        "multiply" => Operation::Combine(CombineOp::Multiply),
+        "mix" => Operation::Combine(CombineOp::Mix(parts.next()?.parse().ok()?)),
+        "max" => Operation::Combine(CombineOp::Max),
+        "min" => Operation::Combine(CombineOp::Min),
+        "crossfade" => {
+            Operation::Crossfade(parts.next()?.parse().ok()?, parts.next()?.parse().ok()?)
+        }
+        _ => return None,
+    };
+    Some(op)
+}
+
+pub fn run(mut args: Vec<String>) {
+    let domain = if take_flag(&mut args, "--magnitude") {
+        CombineDomain::Magnitude
+    } else {
+        CombineDomain::Complex
+    };
+    let engine = take_engine(&mut args, false);
+    let settings = SpectrogramSettings {
+        window_size: take_window(&mut args, 3000),
+        window_pad_amnt: 0,
+    };
+    let sample_rate = take_sample_rate(&mut args);
+
+    let [op, a_path, b_path, out_path] = args.as_slice() else {
+        usage_error(USAGE);
+    };
+    let op = parse_operation(op).unwrap_or_else(|| usage_error(USAGE));
+
+    let (mut a_samples, sr) = load_mono(a_path, sample_rate);
+    let (mut b_samples, _) = load_mono(b_path, Some(sr));
+    pad_to_same_length(&mut a_samples, &mut b_samples);
+
+    let mut a = spectrogram::forward::analyze_mt(&a_samples, &settings, 15).unwrap();
+    let b = spectrogram::forward::analyze_mt(&b_samples, &settings, 15).unwrap();
+
+    match op {
+        Operation::Combine(op) => a.combine(&b, op, domain),
+        Operation::Crossfade(start, end) => {
+            let hop_size = (settings.window_size / 2) as f32;
+            let to_column = |secs: f32| ((secs * sr as f32 / hop_size) as usize).min(a.width);
+            let (start_x, end_x) = (to_column(start), to_column(end));
+            a.crossfade(&b, start_x, end_x.max(start_x), domain);
+        }
+    }
+
+    let reverse = engine.synthesize(&a, &settings, 15);
+    save_wav(out_path, sr, reverse);
+}
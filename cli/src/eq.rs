@@ -0,0 +1,84 @@
+use spectrogram::{
+    SpectrogramSettings,
+    eq::{CurveInterpolation, EqCurve, GainCurve},
+};
+
+use crate::{
+    args::{take_engine, take_sample_rate, take_window, usage_error},
+    audio::{load_mono, save_wav},
+};
+
+const USAGE: &str = "usage: cli eq [--window N] [--sample-rate N] [--engine fft|bins|peaks[:N]] <curve-file> <in> <out.wav>";
+
+// Curve files hold one `HZ DB` breakpoint per line. `interpolation linear` or
+// `interpolation log` (the default) sets how breakpoints are joined, and `at POS` starts a
+// new keyframe at a fraction of the file's length; breakpoints before any `at` belong to
+// a keyframe at 0. `#` starts a comment.
+fn parse_curve_file(text: &str) -> Result<EqCurve, String> {
+    let mut interpolation = CurveInterpolation::Log;
+    let mut keyframes: Vec<(f32, Vec<[f32; 2]>)> = vec![];
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        let err = || format!("line {}: cannot parse `{}`", i + 1, line);
+        let words: Vec<_> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => {}
+            ["interpolation", "linear"] => interpolation = CurveInterpolation::Linear,
+            ["interpolation", "log"] => interpolation = CurveInterpolation::Log,
+            ["at", pos] => keyframes.push((pos.parse().map_err(|_| err())?, vec![])),
+            [hz, db] => {
+                let point = [
+                    hz.parse().map_err(|_| err())?,
+                    db.parse().map_err(|_| err())?,
+                ];
+                if keyframes.is_empty() {
+                    keyframes.push((0f32, vec![]));
+                }
+                keyframes.last_mut().unwrap().1.push(point);
+            }
+            _ => return Err(err()),
+        }
+    }
+
+    keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(EqCurve {
+        keyframes: keyframes
+            .into_iter()
+            .map(|(pos, mut points)| {
+                points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+                (
+                    pos,
+                    GainCurve {
+                        points,
+                        interpolation,
+                    },
+                )
+            })
+            .collect(),
+    })
+}
+
+pub fn run(mut args: Vec<String>) {
+    let engine = take_engine(&mut args, false);
+    let settings = SpectrogramSettings {
+        window_size: take_window(&mut args, 3000),
+        window_pad_amnt: 0,
+    };
+    let sample_rate = take_sample_rate(&mut args);
+
+    let [curve_path, in_path, out_path] = args.as_slice() else {
+        usage_error(USAGE);
+    };
+
+    let text = std::fs::read_to_string(curve_path)
+        .unwrap_or_else(|e| usage_error(&format!("cannot read {}: {}", curve_path, e)));
+    let curve = parse_curve_file(&text).unwrap_or_else(|e| usage_error(&e));
+
+    let (samples, sr) = load_mono(in_path, sample_rate);
+    let mut spec = spectrogram::forward::analyze_mt(&samples, &settings, 15).unwrap();
+    spec.apply_eq(&curve, &settings, sr);
+
+    let reverse = engine.synthesize(&spec, &settings, 15);
+    save_wav(out_path, sr, reverse);
+}
@@ -1,19 +1,50 @@
 use std::{fs::File, time::Duration};
 
-use image::{ImageBuffer, Luma};
+use image::{ImageBuffer, Luma, Rgb};
 use rodio::{Decoder, Source, buffer::SamplesBuffer};
 use spectrogram::{
-    SpectrogramImage, SpectrogramIntensityPlotSettings, SpectrogramPhasePlotSettings,
-    SpectrogramSettings,
+    IntensityScale, SpectrogramImage, SpectrogramIntensityPlotSettings,
+    SpectrogramPhasePlotSettings, SpectrogramSettings, metadata::ReconstructionMetadata,
 };
 
+mod args;
+mod audio;
+mod combine;
+mod eq;
+mod mask;
+mod morph;
+mod multires;
+mod partials;
+mod picture;
+mod reconstruct;
+mod scalogram;
+mod vocode;
+
 fn main() {
     let args: Vec<_> = std::env::args().collect();
 
+    match args.get(1).map(|a| a.as_str()) {
+        Some("combine") => combine::run(args[2..].to_vec()),
+        Some("eq") => eq::run(args[2..].to_vec()),
+        Some("mask") => mask::run(args[2..].to_vec()),
+        Some("morph") => morph::run(args[2..].to_vec()),
+        Some("multires") => multires::run(args[2..].to_vec()),
+        Some("partials") => partials::run(args[2..].to_vec()),
+        Some("picture") => picture::run(args[2..].to_vec()),
+        Some("reconstruct") => reconstruct::run(args[2..].to_vec()),
+        Some("scalogram") => scalogram::run(args[2..].to_vec()),
+        Some("vocode") => vocode::run(args[2..].to_vec()),
+        _ => analyze(args),
+    }
+}
+
+fn analyze(mut args: Vec<String>) {
     let settings = SpectrogramSettings {
         window_size: 3000,
         window_pad_amnt: 0,
     };
+    let sample_rate: Option<u32> = args::take_sample_rate(&mut args);
+    let engine = args::take_engine(&mut args, true);
     println!("{:?}", args);
     let audio: Box<dyn Source>;
     if args.len() >= 2 {
@@ -31,9 +62,13 @@ fn main() {
     //let audio = rodio::source::SineWave::new(1200f32).take_duration(Duration::new(100, 0));
     //rodio::output_to_wav(&mut audio.clone(), "results/original.wav").unwrap();
     let channels = audio.channels();
-    let sr = audio.sample_rate();
+    let mut sr = audio.sample_rate();
     println!("{}", channels);
-    let samples: Vec<_> = audio.step_by(channels as usize).collect();
+    let mut samples: Vec<_> = audio.step_by(channels as usize).collect();
+    if let Some(rate) = sample_rate {
+        samples = spectrogram::resample::resample(&samples, sr, rate);
+        sr = rate;
+    }
 
     let mut orig_orig = SamplesBuffer::new(1, sr, samples.clone());
     rodio::output_to_wav(&mut orig_orig, "results/singlechannel_orig.wav").unwrap();
@@ -47,17 +82,19 @@ fn main() {
     let intensity_settings = SpectrogramIntensityPlotSettings {
         bin_range: [
             0,
-            SpectrogramImage::compute_bin_number(settings.window_size, sr as usize, targ_freq),
+            SpectrogramImage::compute_bin_number(&settings, sr as usize, targ_freq),
         ],
         intensity_range: [0f32, 10f32],
+        scale: IntensityScale::NaturalLog,
     };
 
     let phase_settings = SpectrogramPhasePlotSettings {
         bin_range: intensity_settings.bin_range.clone(),
         lower_seam: 0f32,
+        unwrap_along_time: None,
     };
 
-    let sane_reverse = spectrogram::inverse::inverse_mt(&res, &settings, 2, false);
+    let sane_reverse = engine.with_awful_hack(false).synthesize(&res, &settings, 2);
 
     let mut orig = SamplesBuffer::new(1, sr, sane_reverse);
     rodio::output_to_wav(&mut orig, "results/original_reconstructed.wav").unwrap();
@@ -66,6 +103,22 @@ fn main() {
     let view_bytes = res.create_intensity_bytes(&intensity_settings);
     let view_phase_bytes = res.create_relative_phase_bytes(&phase_settings);
 
+    // Magnitude and phase together, rebuilt from nothing but the colour image.
+    let color_bytes = res.create_hsv_bytes(&intensity_settings, &phase_settings);
+    let mut from_color = SpectrogramImage::new_empty(res.width, res.height);
+    from_color.apply_hsv_bytes(&intensity_settings, &phase_settings, &color_bytes);
+    let color_reverse = engine.synthesize(&from_color, &settings, 15);
+    let mut color_aud = SamplesBuffer::new(1, sr, color_reverse);
+    rodio::output_to_wav(&mut color_aud, "results/color_reconstructed.wav").unwrap();
+    ImageBuffer::<Rgb<u8>, Vec<u8>>::from_vec(
+        res.width as u32,
+        (intensity_settings.bin_range[1] - intensity_settings.bin_range[0]) as u32,
+        color_bytes,
+    )
+    .unwrap()
+    .save("results/color.png")
+    .unwrap();
+
     let masked_phase_bytes = {
         let mut bytes = view_phase_bytes.clone();
         for ind in 0..bytes.len() {
@@ -81,21 +134,24 @@ fn main() {
     // Nuke phase
     res.eliminate_phase();
     //res.apply_random_phases();
-    //res.apply_sinusoidal_phases(settings.window_size);
+    //res.apply_sinusoidal_phases(&settings, SinusoidFrequency::Instantaneous);
     let intens = res.create_intensity_bytes(&intensity_settings);
     res.phaseless_from_intensity_bytes(&intensity_settings, &intens, true);
 
-    let reverse = spectrogram::inverse::inverse_mt(&res, &settings, 15, true);
+    let reverse = engine.synthesize(&res, &settings, 15);
     let mut aud = SamplesBuffer::new(1, sr, reverse);
     rodio::output_to_wav(&mut aud, "results/mywav.wav").unwrap();
 
-    let img_buffer: ImageBuffer<Luma<u8>, Vec<_>> = ImageBuffer::from_vec(
-        res.width as u32,
-        (intensity_settings.bin_range[1] - intensity_settings.bin_range[0]) as u32,
-        view_bytes,
+    // Carries its own settings, so `cli reconstruct` can turn an edited copy back into audio.
+    res.save_intensity_png(
+        "results/dest.png",
+        &ReconstructionMetadata {
+            settings,
+            sample_rate: sr,
+            intensity: intensity_settings,
+        },
     )
     .unwrap();
-    img_buffer.save("results/dest.png").unwrap();
     ImageBuffer::<Luma<u8>, Vec<u8>>::from_vec(
         res.width as u32,
         (intensity_settings.bin_range[1] - intensity_settings.bin_range[0]) as u32,
@@ -0,0 +1,59 @@
+use spectrogram::{
+    SpectrogramImage, SpectrogramSettings,
+    picture::{FrequencyAxis, PictureSettings},
+};
+
+use crate::{
+    args::{take_engine, take_flag, take_option, take_sample_rate, take_window, usage_error},
+    audio::{load_mono, save_wav},
+    picture::parse_pair,
+};
+
+const USAGE: &str = "usage: cli mask [--freqs LO:HI] [--log] [--feather COLUMNS:BINS] [--window N] \
+[--sample-rate N] [--engine fft|bins|peaks[:N]] <in> <mask.png> <kept.wav> [<removed.wav>]";
+
+// Multiplies a file's spectrogram by a greyscale mask stretched over its whole length and
+// `--freqs` (0 Hz to Nyquist by default), then writes what the mask passes and, if asked,
+// what it removes.
+pub fn run(mut args: Vec<String>) {
+    let settings = SpectrogramSettings {
+        window_size: take_window(&mut args, 2048),
+        window_pad_amnt: 0,
+    };
+    let sample_rate = take_sample_rate(&mut args);
+    let freqs = take_option(&mut args, "--freqs")
+        .map(|f| parse_pair(&f).unwrap_or_else(|| usage_error(USAGE)));
+    let log = take_flag(&mut args, "--log");
+    let feather = take_option(&mut args, "--feather")
+        .map(|f| parse_pair(&f).unwrap_or_else(|| usage_error(USAGE)));
+    let engine = take_engine(&mut args, false);
+
+    let (in_path, mask_path, kept_path, removed_path) = match args.as_slice() {
+        [a, b, c] => (a, b, c, None),
+        [a, b, c, d] => (a, b, c, Some(d)),
+        _ => usage_error(USAGE),
+    };
+
+    let (samples, sr) = load_mono(in_path, sample_rate);
+    let spec = spectrogram::forward::analyze_mt(&samples, &settings, 15).unwrap();
+
+    let picture = image::open(mask_path)
+        .unwrap_or_else(|e| usage_error(&format!("cannot open {}: {}", mask_path, e)));
+    let mut placement = PictureSettings::new(settings, sr);
+    placement.frequency_range = freqs.unwrap_or([0f32, sr as f32 / 2f32]);
+    placement.axis = if log {
+        FrequencyAxis::Log
+    } else {
+        FrequencyAxis::Linear
+    };
+    let mut mask = SpectrogramImage::mask_from_picture(&picture, &placement, spec.width);
+    if let Some([time, freq]) = feather {
+        mask.feather_mask(time, freq);
+    }
+
+    let (kept, removed) = spec.split_by_mask(&mask);
+    save_wav(kept_path, sr, engine.synthesize(&kept, &settings, 15));
+    if let Some(removed_path) = removed_path {
+        save_wav(removed_path, sr, engine.synthesize(&removed, &settings, 15));
+    }
+}
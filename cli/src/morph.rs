@@ -0,0 +1,58 @@
+use spectrogram::{
+    SpectrogramSettings,
+    morph::{MorphCurve, MorphSettings},
+};
+
+use crate::{
+    args::{
+        take_engine, take_option, take_parsed_option, take_sample_rate, take_window, usage_error,
+    },
+    audio::{load_mono, pad_to_same_length, save_wav},
+};
+
+const USAGE: &str = "usage: cli morph [--curve POS:AMOUNT,...] [--lifter N] [--partials N] \
+[--window N] [--sample-rate N] [--engine fft|bins|peaks[:N]] <a> <b> <out.wav>";
+
+fn parse_curve(s: &str) -> Option<MorphCurve> {
+    let mut keyframes = vec![];
+    for key in s.split(',') {
+        let (pos, amount) = key.split_once(':')?;
+        keyframes.push([pos.parse().ok()?, amount.parse().ok()?]);
+    }
+    keyframes.sort_by(|a: &[f32; 2], b| a[0].total_cmp(&b[0]));
+    Some(MorphCurve { keyframes })
+}
+
+pub fn run(mut args: Vec<String>) {
+    let defaults = MorphSettings::default();
+    let morph_settings = MorphSettings {
+        curve: take_option(&mut args, "--curve")
+            .map(|c| parse_curve(&c).unwrap_or_else(|| usage_error(USAGE)))
+            .unwrap_or(defaults.curve),
+        envelope_lifter: take_parsed_option(&mut args, "--lifter")
+            .unwrap_or(defaults.envelope_lifter),
+        max_partials: take_parsed_option(&mut args, "--partials").unwrap_or(defaults.max_partials),
+    };
+    let engine = take_engine(&mut args, true);
+    let settings = SpectrogramSettings {
+        window_size: take_window(&mut args, 3000),
+        window_pad_amnt: 0,
+    };
+    let sample_rate = take_sample_rate(&mut args);
+
+    let [a_path, b_path, out_path] = args.as_slice() else {
+        usage_error(USAGE);
+    };
+
+    let (mut a_samples, sr) = load_mono(a_path, sample_rate);
+    let (mut b_samples, _) = load_mono(b_path, Some(sr));
+    pad_to_same_length(&mut a_samples, &mut b_samples);
+
+    let a = spectrogram::forward::analyze_mt(&a_samples, &settings, 15).unwrap();
+    let b = spectrogram::forward::analyze_mt(&b_samples, &settings, 15).unwrap();
+
+    let morphed = spectrogram::morph::morph(&a, &b, &morph_settings, &settings);
+
+    let reverse = engine.synthesize(&morphed, &settings, 15);
+    save_wav(out_path, sr, reverse);
+}
@@ -0,0 +1,100 @@
+use image::{ImageBuffer, Luma};
+use spectrogram::{
+    IntensityScale, SpectrogramSettings,
+    multires::{MultiResolution, ResolutionCombine},
+    resize::ResizeInterpolation,
+};
+
+use crate::{
+    args::{take_engine, take_flag, take_option, take_sample_rate, usage_error},
+    audio::{load_mono, save_wav},
+};
+
+const USAGE: &str = "usage: cli multires [--windows N,N,...] [--crossovers HZ,HZ,...] [--min] \
+[--bands PREFIX] [--sample-rate N] [--engine fft|bins|peaks[:N]] <in> <out.png> [<out.wav>]";
+
+fn parse_list<T: std::str::FromStr>(s: &str) -> Vec<T> {
+    s.split(',')
+        .map(|v| {
+            v.parse()
+                .unwrap_or_else(|_| usage_error(&format!("invalid list entry: {}", v)))
+        })
+        .collect()
+}
+
+// Analyzes a file at several window lengths and writes them combined into one image, by
+// band or by minimum magnitude. The bands can also be resynthesized, each from its own
+// resolution: `--bands PREFIX` writes them to PREFIX-0.wav (lowest) upward, and
+// `<out.wav>` gets their sum.
+pub fn run(mut args: Vec<String>) {
+    let windows: Vec<usize> = take_option(&mut args, "--windows")
+        .map(|w| parse_list(&w))
+        .unwrap_or(vec![4096, 1024, 256]);
+    let crossovers: Option<Vec<f32>> =
+        take_option(&mut args, "--crossovers").map(|c| parse_list(&c));
+    let min = take_flag(&mut args, "--min");
+    let bands_prefix = take_option(&mut args, "--bands");
+    let sample_rate = take_sample_rate(&mut args);
+    let engine = take_engine(&mut args, false);
+
+    let (in_path, png_path, wav_path) = match args.as_slice() {
+        [a, b] => (a, b, None),
+        [a, b, c] => (a, b, Some(c)),
+        _ => usage_error(USAGE),
+    };
+    if windows.is_empty() || windows.iter().any(|&w| w == 0 || w % 2 == 1) {
+        usage_error("--windows must be even positive lengths");
+    }
+    if let Some(crossovers) = &crossovers {
+        if crossovers.len() + 1 != windows.len() {
+            usage_error("--crossovers needs one frequency fewer than --windows has lengths");
+        }
+        if crossovers.first().is_some_and(|&c| c <= 0f32) || !crossovers.is_sorted_by(|a, b| a < b)
+        {
+            usage_error("--crossovers must be positive and ascending");
+        }
+    }
+    let settings: Vec<_> = windows
+        .iter()
+        .map(|&window_size| SpectrogramSettings {
+            window_size,
+            window_pad_amnt: 0,
+        })
+        .collect();
+
+    let (samples, sr) = load_mono(in_path, sample_rate);
+    let analysis = MultiResolution::analyze(&samples, &settings, sr, 15);
+    let crossovers = crossovers.unwrap_or_else(|| analysis.default_crossovers());
+
+    let mode = if min {
+        ResolutionCombine::MinMagnitude
+    } else {
+        ResolutionCombine::BandSplit {
+            crossovers: crossovers.clone(),
+        }
+    };
+    let combined = analysis.combine(&mode, ResizeInterpolation::Linear);
+    let intensity = combined.auto_intensity_settings(
+        [0, combined.height],
+        IntensityScale::dbfs(&analysis.display_settings()),
+        [5f32, 99.9f32],
+    );
+    ImageBuffer::<Luma<u8>, Vec<u8>>::from_vec(
+        combined.width as u32,
+        combined.height as u32,
+        combined.create_intensity_bytes(&intensity),
+    )
+    .unwrap()
+    .save(png_path)
+    .unwrap();
+
+    if let Some(prefix) = bands_prefix {
+        for band in 0..windows.len() {
+            let samples = analysis.resynthesize_band(band, &crossovers, engine, 15);
+            save_wav(&format!("{}-{}.wav", prefix, band), sr, samples);
+        }
+    }
+    if let Some(wav_path) = wav_path {
+        save_wav(wav_path, sr, analysis.resynthesize(&crossovers, engine, 15));
+    }
+}
@@ -0,0 +1,45 @@
+use spectrogram::{
+    SpectrogramSettings,
+    sinusoidal::{PeakTracking, SinusoidalModel},
+};
+
+use crate::{
+    args::{take_noise, take_parsed_option, take_sample_rate, take_window, usage_error},
+    audio::{load_mono, save_wav},
+};
+
+const USAGE: &str = "usage: cli partials [--max N] [--floor DB] [--min-length COLUMNS] \
+[--transpose SEMITONES] [--residual GAIN] [--noise uniform|gaussian|pink] [--seed N] \
+[--window N] [--sample-rate N] <in> <out.wav>";
+
+// Splits a file into partial tracks and a noise residual, optionally transposes the
+// partials, and puts the two back together.
+pub fn run(mut args: Vec<String>) {
+    let defaults = PeakTracking::default();
+    let tracking = PeakTracking {
+        max_count: take_parsed_option(&mut args, "--max").unwrap_or(defaults.max_count),
+        floor_db: take_parsed_option(&mut args, "--floor").unwrap_or(defaults.floor_db),
+        min_columns: take_parsed_option(&mut args, "--min-length").unwrap_or(defaults.min_columns),
+        ..defaults
+    };
+    let transpose: f32 = take_parsed_option(&mut args, "--transpose").unwrap_or(0f32);
+    let residual_gain: f32 = take_parsed_option(&mut args, "--residual").unwrap_or(1f32);
+    let noise = take_noise(&mut args);
+    let settings = SpectrogramSettings {
+        window_size: take_window(&mut args, 2048),
+        window_pad_amnt: 0,
+    };
+    let sample_rate = take_sample_rate(&mut args);
+
+    let [in_path, out_path] = args.as_slice() else {
+        usage_error(USAGE);
+    };
+
+    let (samples, sr) = load_mono(in_path, sample_rate);
+    let spec = spectrogram::forward::analyze_mt(&samples, &settings, 15).unwrap();
+    let mut model = SinusoidalModel::analyze(&spec, &settings, &tracking, 15);
+    println!("{} partials", model.partials.len());
+    model.transpose(transpose);
+
+    save_wav(out_path, sr, model.resynthesize(residual_gain, &noise, 15));
+}
@@ -0,0 +1,68 @@
+use spectrogram::{
+    SpectrogramImage, SpectrogramSettings,
+    picture::{FrequencyAxis, PictureSettings},
+    retrieval::PhaseRetrieval,
+};
+
+use crate::{
+    args::{
+        take_engine, take_flag, take_noise, take_option, take_parsed_option, take_sample_rate,
+        take_window, usage_error,
+    },
+    audio::save_wav,
+};
+
+const USAGE: &str = "usage: cli picture [--duration SECONDS] [--freqs LO:HI] [--linear] \
+[--range LO_DB:HI_DB] [--phase random|peak|pghi|gl:N] [--noise uniform|gaussian|pink] \
+[--seed N] [--window N] [--sample-rate N] [--engine fft|bins|peaks[:N]] <in.png> <out.wav>";
+
+pub(crate) fn parse_pair(s: &str) -> Option<[f32; 2]> {
+    let (a, b) = s.split_once(':')?;
+    Some([a.parse().ok()?, b.parse().ok()?])
+}
+
+fn parse_phase(s: &str) -> Option<PhaseRetrieval> {
+    Some(match s {
+        "random" => PhaseRetrieval::Random,
+        "peak" => PhaseRetrieval::PeakLocked,
+        "pghi" => PhaseRetrieval::Pghi,
+        _ => PhaseRetrieval::GriffinLim {
+            iterations: s.strip_prefix("gl:")?.parse().ok()?,
+        },
+    })
+}
+
+pub fn run(mut args: Vec<String>) {
+    let settings = SpectrogramSettings {
+        window_size: take_window(&mut args, 2048),
+        window_pad_amnt: 0,
+    };
+    let sample_rate = take_sample_rate(&mut args).unwrap_or(44100);
+    let mut picture_settings = PictureSettings::new(settings, sample_rate);
+    if let Some(duration) = take_parsed_option(&mut args, "--duration") {
+        picture_settings.duration = duration;
+    }
+    if let Some(freqs) = take_option(&mut args, "--freqs") {
+        picture_settings.frequency_range = parse_pair(&freqs).unwrap_or_else(|| usage_error(USAGE));
+    }
+    if take_flag(&mut args, "--linear") {
+        picture_settings.axis = FrequencyAxis::Linear;
+    }
+    if let Some(range) = take_option(&mut args, "--range") {
+        picture_settings.intensity_range = parse_pair(&range).unwrap_or_else(|| usage_error(USAGE));
+    }
+    picture_settings.engine = take_engine(&mut args, true);
+    picture_settings.noise = take_noise(&mut args);
+    if let Some(phase) = take_option(&mut args, "--phase") {
+        picture_settings.phase = parse_phase(&phase).unwrap_or_else(|| usage_error(USAGE));
+    }
+
+    let [in_path, out_path] = args.as_slice() else {
+        usage_error(USAGE);
+    };
+
+    let picture = image::open(in_path)
+        .unwrap_or_else(|e| usage_error(&format!("cannot open {}: {}", in_path, e)));
+    let samples = SpectrogramImage::picture_to_audio(&picture, &picture_settings, 15);
+    save_wav(out_path, sample_rate, samples);
+}
@@ -0,0 +1,26 @@
+use spectrogram::{SpectrogramImage, resample::resample};
+
+use crate::{
+    args::{take_engine, take_sample_rate, usage_error},
+    audio::save_wav,
+};
+
+const USAGE: &str =
+    "usage: cli reconstruct [--sample-rate N] [--engine fft|bins|peaks[:N]] <in.png> <out.wav>";
+
+// Resynthesizes an intensity PNG written by `analyze`, using the settings stored in it.
+pub fn run(mut args: Vec<String>) {
+    let engine = take_engine(&mut args, true);
+    let sample_rate = take_sample_rate(&mut args);
+    let [png_path, out_path] = args.as_slice() else {
+        usage_error(USAGE);
+    };
+
+    let (samples, sr) = SpectrogramImage::reconstruct_from_png(png_path, engine, 15)
+        .unwrap_or_else(|e| usage_error(&format!("cannot reconstruct {}: {:?}", png_path, e)));
+    // The image is resynthesized at the rate it was analyzed at, then converted.
+    match sample_rate {
+        Some(rate) => save_wav(out_path, rate, resample(&samples, sr, rate)),
+        None => save_wav(out_path, sr, samples),
+    }
+}
@@ -0,0 +1,72 @@
+use image::{ImageBuffer, Luma};
+use spectrogram::{
+    IntensityScale,
+    wavelet::{CwtSettings, analyze_cwt, inverse_cwt},
+};
+
+use crate::{
+    args::{take_parsed_option, take_sample_rate, usage_error},
+    audio::{load_mono, save_wav},
+};
+
+const USAGE: &str = "usage: cli scalogram [--min HZ] [--max HZ] [--voices N] [--omega N] \
+[--hop N] [--sample-rate N] <in> <out.png> [<out.wav>]";
+
+// Writes a Morlet wavelet scalogram of a file as a greyscale image, lowest frequency at the
+// bottom and a column every `--hop` samples, and optionally what the inverse transform
+// makes of it.
+pub fn run(mut args: Vec<String>) {
+    let defaults = CwtSettings::default();
+    let settings = CwtSettings {
+        min_freq: take_parsed_option(&mut args, "--min").unwrap_or(defaults.min_freq),
+        max_freq: take_parsed_option(&mut args, "--max").unwrap_or(defaults.max_freq),
+        voices_per_octave: take_parsed_option(&mut args, "--voices")
+            .unwrap_or(defaults.voices_per_octave),
+        omega0: take_parsed_option(&mut args, "--omega").unwrap_or(defaults.omega0),
+        hop_size: take_parsed_option(&mut args, "--hop").unwrap_or(defaults.hop_size),
+    };
+    if settings.hop_size == 0 {
+        usage_error("--hop must be positive");
+    }
+    if settings.voices_per_octave == 0 {
+        usage_error("--voices must be positive");
+    }
+    if settings.min_freq <= 0f32 {
+        usage_error("--min must be positive");
+    }
+    if settings.max_freq <= settings.min_freq {
+        usage_error("--max must be above --min");
+    }
+    let sample_rate = take_sample_rate(&mut args);
+
+    let (in_path, png_path, wav_path) = match args.as_slice() {
+        [a, b] => (a, b, None),
+        [a, b, c] => (a, b, Some(c)),
+        _ => usage_error(USAGE),
+    };
+
+    let (samples, sr) = load_mono(in_path, sample_rate);
+    if settings.max_freq >= sr as f32 / 2f32 {
+        usage_error(&format!("--max must be below {} Hz", sr / 2));
+    }
+    let scalogram = analyze_cwt(&samples, &settings, sr, 15);
+    let image = scalogram.image();
+
+    let intensity = image.auto_intensity_settings(
+        [0, image.height],
+        IntensityScale::Decibels { reference: 1f32 },
+        [5f32, 99.9f32],
+    );
+    ImageBuffer::<Luma<u8>, Vec<u8>>::from_vec(
+        image.width as u32,
+        image.height as u32,
+        image.create_intensity_bytes(&intensity),
+    )
+    .unwrap()
+    .save(png_path)
+    .unwrap();
+
+    if let Some(wav_path) = wav_path {
+        save_wav(wav_path, sr, inverse_cwt(&scalogram, 15));
+    }
+}
@@ -0,0 +1,52 @@
+use spectrogram::{
+    SpectrogramSettings,
+    envelope::EnvelopeSmoothing,
+    vocoder::{CrossSynthesisSettings, cross_synthesize},
+};
+
+use crate::{
+    args::{
+        take_engine, take_flag, take_parsed_option, take_sample_rate, take_window, usage_error,
+    },
+    audio::{load_mono, pad_to_same_length, save_wav},
+};
+
+const USAGE: &str = "usage: cli vocode [--bands N | --lifter N] [--no-flatten] [--window N] \
+[--sample-rate N] [--engine fft|bins|peaks[:N]] <modulator> <carrier> <out.wav>";
+
+pub fn run(mut args: Vec<String>) {
+    let bands = take_parsed_option(&mut args, "--bands");
+    let lifter = take_parsed_option(&mut args, "--lifter");
+    let smoothing = match (bands, lifter) {
+        (Some(_), Some(_)) => usage_error(USAGE),
+        (Some(count), None) => EnvelopeSmoothing::Bands { count },
+        (None, Some(lifter)) => EnvelopeSmoothing::Cepstral { lifter },
+        (None, None) => CrossSynthesisSettings::default().smoothing,
+    };
+    let vocoder_settings = CrossSynthesisSettings {
+        smoothing,
+        flatten_carrier: !take_flag(&mut args, "--no-flatten"),
+    };
+    let engine = take_engine(&mut args, false);
+    let settings = SpectrogramSettings {
+        window_size: take_window(&mut args, 2048),
+        window_pad_amnt: 0,
+    };
+    let sample_rate = take_sample_rate(&mut args);
+
+    let [modulator_path, carrier_path, out_path] = args.as_slice() else {
+        usage_error(USAGE);
+    };
+
+    let (mut modulator_samples, sr) = load_mono(modulator_path, sample_rate);
+    let (mut carrier_samples, _) = load_mono(carrier_path, Some(sr));
+    pad_to_same_length(&mut modulator_samples, &mut carrier_samples);
+
+    let modulator = spectrogram::forward::analyze_mt(&modulator_samples, &settings, 15).unwrap();
+    let carrier = spectrogram::forward::analyze_mt(&carrier_samples, &settings, 15).unwrap();
+
+    let vocoded = cross_synthesize(&modulator, &carrier, &vocoder_settings);
+
+    let reverse = engine.synthesize(&vocoded, &settings, 15);
+    save_wav(out_path, sr, reverse);
+}
@@ -6,7 +6,8 @@ use egui_file_dialog::FileDialog;
 use image::{EncodableLayout, ImageBuffer, Luma};
 use rodio::{OutputStream, Source, buffer::SamplesBuffer};
 use spectrogram::{
-    SpectrogramIntensityPlotSettings, SpectrogramPhasePlotSettings, SpectrogramSettings,
+    IntensityScale, SinusoidFrequency, SpectrogramPhasePlotSettings, SpectrogramSettings,
+    additive::SynthesisEngine,
 };
 
 use crate::app::editor_from_scratch::MyEditor;
@@ -19,6 +20,7 @@ pub struct SpectrogramApp {
     samples: Vec<f32>,
     image: TextureHandle,
     sized_tx: Option<SizedTexture>,
+    engine: SynthesisEngine,
 
     editor: editor_from_scratch::MyEditor,
 }
@@ -36,7 +38,17 @@ impl SpectrogramApp {
                 TextureOptions::NEAREST,
             ),
             sized_tx: None,
-            editor: MyEditor::new(cc, 65, 4000, 44100, 2000f32),
+            engine: SynthesisEngine::InverseFft { awful_hack: true },
+            editor: MyEditor::new(
+                cc,
+                65,
+                SpectrogramSettings {
+                    window_size: 4000,
+                    window_pad_amnt: 0,
+                },
+                44100,
+                2000f32,
+            ),
         }
     }
 
@@ -57,25 +69,30 @@ impl SpectrogramApp {
         self.samples = samples;
         let mut res = spectrogram::forward::analyze_mt(&self.samples, &settings, 15).unwrap();
         println!("Spectrogram made");
-        let view_bytes = res.create_intensity_bytes(&SpectrogramIntensityPlotSettings {
-            bin_range: [0, 100],
-            intensity_range: [-3f32, 10f32],
-        });
+        let view_bytes = res.create_intensity_bytes(&res.auto_intensity_settings(
+            [0, 100],
+            IntensityScale::dbfs(&settings),
+            [5f32, 99.9f32],
+        ));
         let view_phase_bytes = res.create_phase_bytes(&SpectrogramPhasePlotSettings {
             bin_range: [0, 100],
             lower_seam: 0f32,
+            unwrap_along_time: None,
         });
 
-        let sane_reverse = spectrogram::inverse::inverse_mt(&res, &settings, 4, false);
+        let sane_reverse = self
+            .engine
+            .with_awful_hack(false)
+            .synthesize(&res, &settings, 4);
 
         // Nuke phase
         res.eliminate_phase();
         //res.apply_random_phases();
-        res.apply_sinusoidal_phases(settings.window_size);
+        res.apply_sinusoidal_phases(&settings, SinusoidFrequency::Instantaneous);
 
         //egui::containers::ScrollArea::both().show(ui, add_contents);
 
-        let reverse = spectrogram::inverse::inverse_mt(&res, &settings, 4, true);
+        let reverse = self.engine.synthesize(&res, &settings, 4);
         let mut aud = SamplesBuffer::new(1, sr, reverse);
         rodio::output_to_wav(&mut aud, "results/mywav.wav").unwrap();
 
@@ -97,6 +114,7 @@ impl SpectrogramApp {
         let view_screwed_up_phase_bytes = res.create_phase_bytes(&SpectrogramPhasePlotSettings {
             bin_range: [0, 100],
             lower_seam: 0f32,
+            unwrap_along_time: None,
         });
         ImageBuffer::<Luma<u8>, Vec<u8>>::from_vec(
             res.width as u32,
@@ -1,29 +1,64 @@
 use std::{fs::File, io::Read, sync::Arc};
 
 use egui::{
-    Color32, Image, ImageSource, Sense, TextureHandle, TextureOptions, Vec2,
+    Color32, Image, ImageSource, Rect, Sense, Stroke, StrokeKind, TextureHandle, TextureOptions,
+    Vec2,
     load::{ImagePoll, SizedTexture},
+    pos2,
     scroll_area::ScrollSource,
     vec2,
 };
 use egui_file_dialog::FileDialog;
-use rodio::{OutputStream, buffer::SamplesBuffer};
+use rodio::{OutputStream, Source, buffer::SamplesBuffer};
 use rustfft::num_complex::{Complex, Complex32};
 use spectrogram::{
-    SpectrogramImage, SpectrogramIntensityPlotSettings, SpectrogramSettings, UThing,
+    IntensityScale, SpectrogramImage, SpectrogramIntensityPlotSettings, SpectrogramRegion,
+    SpectrogramSettings, UThing,
+    additive::{OscillatorBank, SynthesisEngine},
+    eq::{CurveInterpolation, EqCurve, GainCurve},
+    filter::{FilterPhase, MagnitudeFilter},
+    forward,
+    freeze::FreezeContinuation,
+    noise::RandomPhases,
+    resample::resample,
+    resize::ResizeInterpolation,
+    sinusoidal::{PeakTracking, SinusoidalModel},
+    text::{self, TextPlacement},
 };
 
 use crate::app::editor_from_scratch::drawing::{
     Brush, radius_brush::RadiusBrush, solid_mag_brush::SolidMagBrush,
 };
 
+#[derive(Clone, Copy, PartialEq)]
+enum EditorTool {
+    Paint,
+    // Drag over a region to sustain its first column across it.
+    Freeze,
+    // Drag over a region to blur it along time.
+    Smear,
+    // Edit a gain curve drawn over the spectrogram: frequency up, gain across.
+    Eq,
+    // Click to write a line of text with its bottom left corner there.
+    Text,
+    // Paint the mask layer with the left button, erase it with the right.
+    Mask,
+    // Drag a tracked partial up or down to transpose it alone.
+    Partials,
+}
+
+// Gain at the left and right edges of the EQ overlay.
+const EQ_RANGE_DB: f32 = 24f32;
+
 pub struct MyEditor {
     image: TextureHandle,
     sized_tx: Option<SizedTexture>,
     spectrogram: SpectrogramImage,
     samples: Option<Vec<f32>>,
     width: usize,
-    window_len: usize,
+    settings: SpectrogramSettings,
+    // Highest frequency shown; `img_height` is the bin count below it.
+    max_freq: f32,
     img_height: usize,
     stream: OutputStream,
 
@@ -41,6 +76,37 @@ pub struct MyEditor {
 
     primary_brush: Box<dyn Brush>,
     secondary_brush: Box<dyn Brush>,
+
+    tool: EditorTool,
+    // Normalized start and current pointer positions of a region drag.
+    region_drag: Option<[Vec2; 2]>,
+    freeze_continuation: FreezeContinuation,
+    smear_radius: f32,
+    eq_curve: GainCurve,
+    // Breakpoint being dragged in the EQ overlay.
+    eq_dragging: Option<usize>,
+    text: String,
+    // Height of the text in bins.
+    text_size: f32,
+    engine: SynthesisEngine,
+    // Resolution typed into the controls, applied on demand.
+    pending_settings: SpectrogramSettings,
+    pending_width: usize,
+    resize_interpolation: ResizeInterpolation,
+
+    // Gains over the spectrogram, the same size as it, tinted red on screen. None until
+    // painted on.
+    mask: Option<SpectrogramImage>,
+    mask_radius: f32,
+    // Blur in columns and unpadded bins applied to the mask before it is used.
+    mask_feather: f32,
+    audio_picker: FileDialog,
+    // Why the last recording couldn't be loaded.
+    load_error: Option<String>,
+    // Partials tracked from the spectrogram, dropped whenever it changes size.
+    model: Option<SinusoidalModel>,
+    // Partial being dragged and the bin the pointer was last at.
+    partial_drag: Option<(usize, f32)>,
 }
 
 mod drawing;
@@ -49,13 +115,14 @@ impl MyEditor {
     pub fn new(
         cc: &eframe::CreationContext,
         width: usize,
-        window_len: usize,
+        settings: SpectrogramSettings,
         sample_rate: usize,
         max_freq: f32,
     ) -> Self {
-        let height = window_len / 2 + 1;
-        let max_bin = SpectrogramImage::compute_bin_number(window_len, sample_rate, max_freq);
-        let img_height = max_bin;
+        let height = settings.bin_count();
+        // At least one bin, however small the window or high the rate.
+        let img_height = SpectrogramImage::compute_bin_number(&settings, sample_rate, max_freq)
+            .clamp(1, settings.bin_count());
         let default_bght = 20f32;
         Self {
             image: cc.egui_ctx.load_texture(
@@ -74,6 +141,7 @@ impl MyEditor {
             intensity_settings: SpectrogramIntensityPlotSettings {
                 bin_range: [0, img_height],
                 intensity_range: [0f32, 10f32],
+                scale: IntensityScale::NaturalLog,
             },
             layout_img: None,
             sized_tx: None,
@@ -82,23 +150,450 @@ impl MyEditor {
             sample_rate,
             img_height,
             file_picker: FileDialog::new(),
-            window_len,
+            settings,
+            max_freq,
+            pending_settings: settings,
+            pending_width: width,
+            resize_interpolation: ResizeInterpolation::Linear,
             stream: rodio::OutputStreamBuilder::open_default_stream().unwrap(),
             samples: None,
             scale: vec2(15f32, 15f32),
 
             primary_brush: Box::new(RadiusBrush::new(default_bght, 1f32)), //Box::new(SolidMagBrush::new(default_bght)),
             secondary_brush: Box::new(SolidMagBrush::new(0f32)),
+
+            tool: EditorTool::Paint,
+            region_drag: None,
+            freeze_continuation: FreezeContinuation::PhaseAdvance,
+            smear_radius: 2f32,
+            eq_curve: GainCurve::flat(),
+            eq_dragging: None,
+            text: "hello".to_string(),
+            text_size: 14f32,
+            engine: SynthesisEngine::InverseFft { awful_hack: true },
+            mask: None,
+            mask_radius: 4f32,
+            mask_feather: 1f32,
+            audio_picker: FileDialog::new(),
+            load_error: None,
+            model: None,
+            partial_drag: None,
         }
     }
 
+    fn spectrogram_settings(&self) -> SpectrogramSettings {
+        self.settings
+    }
+
+    // Converts the spectrogram to `settings`, keeping the same sound and the same
+    // frequency range on screen. Only padding changing can be done exactly.
+    fn change_settings(&mut self, settings: SpectrogramSettings) {
+        self.spectrogram = match self.spectrogram.repad(&self.settings, &settings) {
+            Some(repadded) => repadded,
+            None => self.spectrogram.convert_settings(
+                &self.settings,
+                &settings,
+                self.resize_interpolation,
+            ),
+        };
+        self.settings = settings;
+        self.resized();
+    }
+
+    // Converts the spectrogram to `sample_rate`, keeping the same sound. Playback and
+    // every frequency shown follow.
+    fn change_sample_rate(&mut self, sample_rate: usize) {
+        self.spectrogram = self.spectrogram.convert_sample_rate(
+            self.sample_rate as u32,
+            sample_rate as u32,
+            self.resize_interpolation,
+        );
+        self.sample_rate = sample_rate;
+        self.resized();
+    }
+
+    // Stretches the spectrogram in time to `width` columns.
+    fn change_width(&mut self, width: usize) {
+        self.spectrogram = self
+            .spectrogram
+            .resize_time(width, self.resize_interpolation);
+        self.resized();
+    }
+
+    fn resized(&mut self) {
+        self.width = self.spectrogram.width;
+        self.img_height =
+            SpectrogramImage::compute_bin_number(&self.settings, self.sample_rate, self.max_freq)
+                .clamp(1, self.settings.bin_count());
+        self.intensity_settings.bin_range = [0, self.img_height];
+        self.pending_width = self.width;
+        self.pending_settings = self.settings;
+        self.samples = None;
+        self.sized_tx = None;
+        self.mask = None;
+        self.model = None;
+        self.reset_img();
+    }
+
+    // Replaces the spectrogram with an analysis of an audio file's first channel,
+    // resampled to the editor's rate. On failure the spectrogram is left as it was.
+    fn load_recording(&mut self, path: std::path::PathBuf) -> Result<(), String> {
+        let file = File::open(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let audio =
+            rodio::Decoder::try_from(file).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let channels = audio.channels() as usize;
+        let sr = audio.sample_rate();
+        let samples: Vec<f32> = audio.step_by(channels).collect();
+        let samples = resample(&samples, sr, self.sample_rate as u32);
+        self.spectrogram = forward::analyze_mt(&samples, &self.settings, 4)
+            .ok_or_else(|| format!("{}: too short to analyze", path.display()))?;
+        self.resized();
+        Ok(())
+    }
+
+    // Sets the mask to `gain` over a disc around `norm`, `mask_radius` columns across.
+    fn paint_mask(&mut self, norm: Vec2, gain: f32) {
+        let mask = self
+            .mask
+            .get_or_insert_with(|| self.spectrogram.uniform_mask(0f32));
+        let stretch = self.settings.padding_factor();
+        let center = [norm.x * mask.width as f32, norm.y * self.img_height as f32];
+        let r = self.mask_radius;
+        let columns =
+            (center[0] - r).max(0f32) as usize..((center[0] + r).ceil() as usize).min(mask.width);
+        let bins = (center[1] - r * stretch).max(0f32) as usize
+            ..((center[1] + r * stretch).ceil() as usize).min(self.img_height);
+        for x in columns {
+            for y in bins.clone() {
+                let dx = x as f32 - center[0];
+                let dy = (y as f32 - center[1]) / stretch;
+                if dx * dx + dy * dy <= r * r {
+                    *mask.mut_get_at(x, y) = Complex::from(gain);
+                }
+            }
+        }
+    }
+
+    // The mask as it is applied: feathered.
+    fn feathered_mask(&self) -> Option<SpectrogramImage> {
+        let mut mask = self.mask.clone()?;
+        if self.mask_feather > 0f32 {
+            mask.feather_mask(
+                self.mask_feather,
+                self.mask_feather * self.settings.padding_factor(),
+            );
+        }
+        Some(mask)
+    }
+
+    // What the mask passes, or with `removed` what it takes out.
+    fn masked(&self, removed: bool) -> Option<SpectrogramImage> {
+        let (kept, rest) = self.spectrogram.split_by_mask(&self.feathered_mask()?);
+        Some(if removed { rest } else { kept })
+    }
+
+    fn play_masked(&mut self, removed: bool) {
+        if let Some(img) = self.masked(removed) {
+            let samples = self.engine.synthesize(&img, &self.settings, 4);
+            let buffer = SamplesBuffer::new(1, self.sample_rate as u32, samples);
+            self.stream.mixer().add(buffer);
+        }
+    }
+
+    fn region_from_norms(&self, a: Vec2, b: Vec2) -> SpectrogramRegion {
+        let min = a.min(b);
+        let max = a.max(b);
+        // A drag ending on the right or top edge lands on the last column or bin.
+        let to_column =
+            |n: f32| ((n * self.width as f32) as usize).min(self.width.saturating_sub(1));
+        let to_bin =
+            |n: f32| ((n * self.img_height as f32) as usize).min(self.img_height.saturating_sub(1));
+        SpectrogramRegion {
+            columns: to_column(min.x)..(to_column(max.x) + 1).min(self.width),
+            bins: to_bin(min.y)..(to_bin(max.y) + 1).min(self.img_height),
+        }
+    }
+
+    fn apply_region_tool(&mut self, region: SpectrogramRegion) {
+        let settings = self.spectrogram_settings();
+        match self.tool {
+            EditorTool::Paint
+            | EditorTool::Eq
+            | EditorTool::Text
+            | EditorTool::Mask
+            | EditorTool::Partials => {}
+            EditorTool::Freeze => {
+                let source = region.columns.start..region.columns.start + 1;
+                self.spectrogram
+                    .freeze(source, &region, self.freeze_continuation, &settings);
+            }
+            EditorTool::Smear => self
+                .spectrogram
+                .smear(&region, self.smear_radius, &settings),
+        }
+    }
+
+    fn drag_region(&mut self, resp: &egui::Response, ui: &mut egui::Ui, changed: &mut bool) {
+        let to_norm = |p: egui::Pos2| {
+            let norm =
+                ((p - resp.rect.min) / resp.rect.size()).clamp(Vec2::ZERO, Vec2::splat(1f32));
+            vec2(norm.x, 1f32 - norm.y)
+        };
+
+        if let Some(p) = resp.interact_pointer_pos() {
+            if resp.drag_started() {
+                self.region_drag = Some([to_norm(p); 2]);
+            } else if let Some(drag) = &mut self.region_drag {
+                drag[1] = to_norm(p);
+            }
+        }
+
+        if resp.drag_stopped()
+            && let Some([a, b]) = self.region_drag.take()
+        {
+            self.apply_region_tool(self.region_from_norms(a, b));
+            *changed = true;
+        }
+
+        if let Some([a, b]) = self.region_drag {
+            let to_screen = |n: Vec2| {
+                pos2(
+                    resp.rect.min.x + n.x * resp.rect.width(),
+                    resp.rect.min.y + (1f32 - n.y) * resp.rect.height(),
+                )
+            };
+            ui.painter().rect_stroke(
+                Rect::from_two_pos(to_screen(a), to_screen(b)),
+                0f32,
+                Stroke::new(1f32, Color32::YELLOW),
+                StrokeKind::Inside,
+            );
+        }
+    }
+
+    // Maps a normalized overlay position to an `[hz, db]` breakpoint and back.
+    fn eq_point_from_norm(&self, norm: Vec2) -> [f32; 2] {
+        let hz = norm.y * self.img_height as f32 * self.sample_rate as f32
+            / self.settings.fft_len() as f32;
+        [hz, (norm.x * 2f32 - 1f32) * EQ_RANGE_DB]
+    }
+
+    fn eq_norm_from_point(&self, [hz, db]: [f32; 2]) -> Vec2 {
+        let y = self.settings.frequency_bin(hz, self.sample_rate as u32) / self.img_height as f32;
+        vec2((db / EQ_RANGE_DB + 1f32) / 2f32, y)
+    }
+
+    fn edit_eq_curve(&mut self, resp: &egui::Response, ui: &mut egui::Ui) {
+        let to_norm = |p: egui::Pos2| {
+            let norm =
+                ((p - resp.rect.min) / resp.rect.size()).clamp(Vec2::ZERO, Vec2::splat(1f32));
+            vec2(norm.x, 1f32 - norm.y)
+        };
+        let to_screen = |n: Vec2| {
+            pos2(
+                resp.rect.min.x + n.x * resp.rect.width(),
+                resp.rect.min.y + (1f32 - n.y) * resp.rect.height(),
+            )
+        };
+        let nearest = |editor: &Self, p: egui::Pos2| {
+            editor
+                .eq_curve
+                .points
+                .iter()
+                .map(|pt| to_screen(editor.eq_norm_from_point(*pt)).distance(p))
+                .enumerate()
+                .filter(|(_, d)| *d < 10f32)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(i, _)| i)
+        };
+
+        if let Some(p) = resp.interact_pointer_pos() {
+            let point = self.eq_point_from_norm(to_norm(p));
+            if resp.drag_started_by(egui::PointerButton::Secondary) {
+                if let Some(i) = nearest(self, p) {
+                    self.eq_curve.points.remove(i);
+                }
+            } else if resp.drag_started_by(egui::PointerButton::Primary) {
+                self.eq_dragging = nearest(self, p).or_else(|| {
+                    let points = &mut self.eq_curve.points;
+                    let i = points.partition_point(|pt| pt[0] < point[0]);
+                    points.insert(i, point);
+                    Some(i)
+                });
+            } else if let Some(i) = self.eq_dragging {
+                // Breakpoints stay sorted: a dragged one can't pass its neighbours.
+                let points = &mut self.eq_curve.points;
+                let lo = if i > 0 { points[i - 1][0] } else { 0f32 };
+                let hi = points.get(i + 1).map_or(f32::MAX, |pt| pt[0]);
+                points[i] = [point[0].clamp(lo, hi), point[1]];
+            }
+        }
+        if resp.drag_stopped() {
+            self.eq_dragging = None;
+        }
+
+        let painter = ui.painter();
+        let zero_db = self.eq_norm_from_point([0f32, 0f32]).x;
+        painter.line_segment(
+            [
+                to_screen(vec2(zero_db, 0f32)),
+                to_screen(vec2(zero_db, 1f32)),
+            ],
+            Stroke::new(1f32, Color32::DARK_GRAY),
+        );
+        let steps = 256;
+        let curve = (0..=steps)
+            .map(|i| {
+                let y = i as f32 / steps as f32;
+                let hz = self.eq_point_from_norm(vec2(0f32, y))[0];
+                let db = self.eq_curve.db_at(hz).clamp(-EQ_RANGE_DB, EQ_RANGE_DB);
+                to_screen(self.eq_norm_from_point([hz, db]))
+            })
+            .collect();
+        painter.line(curve, Stroke::new(1.5f32, Color32::LIGHT_BLUE));
+        for pt in &self.eq_curve.points {
+            painter.circle_filled(
+                to_screen(self.eq_norm_from_point(*pt)),
+                4f32,
+                Color32::LIGHT_BLUE,
+            );
+        }
+    }
+
+    fn analyze_partials(&mut self) {
+        self.model = Some(SinusoidalModel::analyze(
+            &self.spectrogram,
+            &self.settings,
+            &PeakTracking::default(),
+            4,
+        ));
+    }
+
+    fn play_partials(&mut self) {
+        if let Some(model) = &self.model {
+            let samples = model.resynthesize(1f32, &RandomPhases::default(), 4);
+            let buffer = SamplesBuffer::new(1, self.sample_rate as u32, samples);
+            self.stream.mixer().add(buffer);
+        }
+    }
+
+    // Picks the partial under the pointer on drag start and transposes it as the pointer
+    // moves up or down. Tracks are drawn over the image, the picked one highlighted.
+    fn edit_partials(&mut self, resp: &egui::Response, ui: &mut egui::Ui) {
+        let Some(model) = &mut self.model else {
+            return;
+        };
+        // Pixel centres sit half a column or bin in.
+        let to_point = |p: egui::Pos2| {
+            let norm = (p - resp.rect.min) / resp.rect.size();
+            [
+                norm.x * self.width as f32 - 0.5f32,
+                (1f32 - norm.y) * self.img_height as f32 - 0.5f32,
+            ]
+        };
+        let to_screen = |x: f32, bin: f32| {
+            pos2(
+                resp.rect.min.x + (x + 0.5f32) / self.width as f32 * resp.rect.width(),
+                resp.rect.max.y - (bin + 0.5f32) / self.img_height as f32 * resp.rect.height(),
+            )
+        };
+
+        if let Some(p) = resp.interact_pointer_pos() {
+            let [x, bin] = to_point(p);
+            if resp.drag_started() {
+                let reach = 3f32 * self.settings.padding_factor();
+                self.partial_drag = model
+                    .partial_near(x.round().max(0f32) as usize, bin, reach)
+                    .map(|i| (i, bin));
+            } else if let Some((i, last)) = &mut self.partial_drag
+                && bin > 0f32
+                && *last > 0f32
+            {
+                model.partials[*i].transpose(12f32 * (bin / *last).log2());
+                *last = bin;
+            }
+        }
+        if resp.drag_stopped() {
+            self.partial_drag = None;
+        }
+
+        let painter = ui.painter();
+        for (i, partial) in model.partials.iter().enumerate() {
+            let color = if self.partial_drag.is_some_and(|(j, _)| i == j) {
+                Color32::YELLOW
+            } else {
+                Color32::LIGHT_GREEN
+            };
+            let track = partial
+                .bins
+                .iter()
+                .enumerate()
+                .map(|(j, &bin)| to_screen((partial.first_column + j) as f32, bin))
+                .collect();
+            painter.line(track, Stroke::new(1.5f32, color));
+        }
+    }
+
+    fn apply_eq(&mut self) {
+        self.spectrogram.apply_eq(
+            &EqCurve::constant(self.eq_curve.clone()),
+            &self.spectrogram_settings(),
+            self.sample_rate as u32,
+        );
+        self.samples = None;
+        self.sized_tx = None;
+        self.reset_img();
+    }
+
+    // Writes `self.text` with its bottom left corner at a normalized image position. Font
+    // pixels come out square in columns by bins, like the image is displayed.
+    fn place_text(&mut self, norm: Vec2) {
+        let settings = self.spectrogram_settings();
+        let sr = self.sample_rate as u32;
+        let pixel_size = self.text_size / text::GLYPH_ROWS as f32;
+        let columns = text::line_columns(&self.text) as f32 * pixel_size;
+
+        // Edges sit half a column or bin outside the first and last ones covered.
+        let column_time = |c: f32| (c + 1f32) * settings.hop_size() as f32 / sr as f32;
+        let bin_frequency = |b: f32| b * sr as f32 / settings.fft_len() as f32;
+        let x0 = norm.x * self.width as f32 - 0.5f32;
+        let y0 = norm.y * self.img_height as f32 - 0.5f32;
+        let placement = TextPlacement {
+            time_range: [column_time(x0), column_time(x0 + columns)],
+            frequency_range: [bin_frequency(y0), bin_frequency(y0 + self.text_size)],
+            magnitude: self.default_brightness,
+        };
+        self.spectrogram
+            .draw_text(&self.text, &placement, &settings, sr);
+    }
+
     fn reset_img(&mut self) {
         let colors = self
             .spectrogram
             .create_intensity_bytes(&self.intensity_settings)
             .iter()
             .map(|f| Color32::from_rgb(*f, *f, *f))
-            .collect();
+            .collect::<Vec<_>>();
+        let colors = match &self.mask {
+            Some(mask) => {
+                let gains = mask.create_intensity_bytes(&SpectrogramIntensityPlotSettings {
+                    bin_range: self.intensity_settings.bin_range,
+                    intensity_range: [0f32, 1f32],
+                    scale: IntensityScale::Linear,
+                });
+                colors
+                    .into_iter()
+                    .zip(gains)
+                    .map(|(c, g)| {
+                        let g = g as f32 / 255f32;
+                        let v = c.r() as f32;
+                        let dim = (v * (1f32 - 0.5 * g)) as u8;
+                        Color32::from_rgb((v + (255f32 - v) * 0.5 * g) as u8, dim, dim)
+                    })
+                    .collect()
+            }
+            None => colors,
+        };
 
         let img = egui::ColorImage::new([self.width, self.img_height], colors);
         self.image.set(img, TextureOptions::NEAREST);
@@ -110,8 +605,39 @@ impl MyEditor {
         ui: &mut egui::Ui,
         changed: &mut bool,
     ) {
-        let resp = ui.add(img.sense(Sense::drag()));
-        if resp.dragged() {
+        let sense = if self.tool == EditorTool::Text {
+            Sense::click()
+        } else {
+            Sense::drag()
+        };
+        let resp = ui.add(img.sense(sense));
+        if self.tool == EditorTool::Text {
+            if resp.clicked()
+                && let Some(p) = resp.interact_pointer_pos()
+            {
+                let norm = (p - resp.rect.min) / resp.rect.size();
+                self.place_text(vec2(norm.x, 1f32 - norm.y));
+                *changed = true;
+            }
+        } else if self.tool == EditorTool::Eq {
+            self.edit_eq_curve(&resp, ui);
+        } else if self.tool == EditorTool::Partials {
+            self.edit_partials(&resp, ui);
+        } else if self.tool == EditorTool::Mask {
+            if resp.dragged()
+                && let Some(p) = resp.interact_pointer_pos()
+            {
+                let norm = (p - resp.rect.min) / resp.rect.size();
+                let norm = vec2(norm.x, 1f32 - norm.y);
+                if norm.x >= 0f32 && norm.x < 1f32 && norm.y >= 0f32 && norm.y < 1f32 {
+                    let erase = resp.dragged_by(egui::PointerButton::Secondary);
+                    self.paint_mask(norm, if erase { 0f32 } else { 1f32 });
+                    *changed = true;
+                }
+            }
+        } else if self.tool != EditorTool::Paint {
+            self.drag_region(&resp, ui, changed);
+        } else if resp.dragged() {
             if let Some(p) = resp.interact_pointer_pos() {
                 let norm = (p - resp.rect.min) / resp.rect.size();
                 let norm = vec2(norm.x, 1f32 - norm.y);
@@ -122,7 +648,12 @@ impl MyEditor {
                         &self.primary_brush
                     };
 
-                    brush_to_use.apply(&mut self.spectrogram, [0, self.img_height], norm);
+                    brush_to_use.apply(
+                        &mut self.spectrogram,
+                        &self.settings,
+                        [0, self.img_height],
+                        norm,
+                    );
                     *changed = true;
                 }
             }
@@ -137,12 +668,124 @@ impl MyEditor {
     }
 
     pub fn show(&mut self, ui: &mut egui::Ui) {
-        if ui.button("Play").clicked() {
-            self.play();
-        }
-        if ui.button("Choose overlay").clicked() {
-            self.file_picker.pick_file();
-        }
+        ui.horizontal(|ui| {
+            if ui.button("Play").clicked() {
+                self.play();
+            }
+            let engines = [
+                (
+                    SynthesisEngine::InverseFft { awful_hack: true },
+                    "Inverse FFT",
+                ),
+                (
+                    SynthesisEngine::Additive(OscillatorBank::EveryBin),
+                    "Oscillator per bin",
+                ),
+                (
+                    SynthesisEngine::Additive(OscillatorBank::Peaks { max_count: 64 }),
+                    "Oscillator per peak",
+                ),
+            ];
+            for (engine, label) in engines {
+                if ui.radio_value(&mut self.engine, engine, label).changed() {
+                    self.samples = None;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Choose overlay").clicked() {
+                self.file_picker.pick_file();
+            }
+            if ui.button("Load recording").clicked() {
+                self.audio_picker.pick_file();
+            }
+            if let Some(error) = &self.load_error {
+                ui.colored_label(Color32::RED, error);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.tool, EditorTool::Paint, "Paint");
+            ui.selectable_value(&mut self.tool, EditorTool::Freeze, "Freeze");
+            ui.selectable_value(&mut self.tool, EditorTool::Smear, "Smear");
+            ui.selectable_value(&mut self.tool, EditorTool::Eq, "EQ");
+            ui.selectable_value(&mut self.tool, EditorTool::Text, "Text");
+            ui.selectable_value(&mut self.tool, EditorTool::Mask, "Mask");
+            ui.selectable_value(&mut self.tool, EditorTool::Partials, "Partials");
+            match self.tool {
+                EditorTool::Paint => {}
+                EditorTool::Freeze => {
+                    ui.radio_value(
+                        &mut self.freeze_continuation,
+                        FreezeContinuation::PhaseAdvance,
+                        "Phase advance",
+                    );
+                    ui.radio_value(
+                        &mut self.freeze_continuation,
+                        FreezeContinuation::RandomPhase { seed: None },
+                        "Random phase",
+                    );
+                }
+                EditorTool::Smear => {
+                    ui.add(
+                        egui::Slider::new(&mut self.smear_radius, 0.5f32..=20f32).text("Radius"),
+                    );
+                }
+                EditorTool::Eq => {
+                    ui.radio_value(
+                        &mut self.eq_curve.interpolation,
+                        CurveInterpolation::Log,
+                        "Log",
+                    );
+                    ui.radio_value(
+                        &mut self.eq_curve.interpolation,
+                        CurveInterpolation::Linear,
+                        "Linear",
+                    );
+                    if ui.button("Apply EQ").clicked() {
+                        self.apply_eq();
+                    }
+                    if ui.button("Reset curve").clicked() {
+                        self.eq_curve.points.clear();
+                    }
+                }
+                EditorTool::Text => {
+                    ui.text_edit_singleline(&mut self.text);
+                    ui.add(egui::Slider::new(&mut self.text_size, 7f32..=70f32).text("Height"));
+                }
+                EditorTool::Mask => {
+                    ui.add(egui::Slider::new(&mut self.mask_radius, 1f32..=40f32).text("Radius"));
+                    ui.add(egui::Slider::new(&mut self.mask_feather, 0f32..=10f32).text("Feather"));
+                    if ui.button("Play isolated").clicked() {
+                        self.play_masked(false);
+                    }
+                    if ui.button("Play removed").clicked() {
+                        self.play_masked(true);
+                    }
+                    for (label, removed) in [("Keep masked", false), ("Remove masked", true)] {
+                        if ui.button(label).clicked()
+                            && let Some(img) = self.masked(removed)
+                        {
+                            self.spectrogram = img;
+                            self.mask = None;
+                            self.samples = None;
+                            self.reset_img();
+                        }
+                    }
+                    if ui.button("Clear mask").clicked() {
+                        self.mask = None;
+                        self.reset_img();
+                    }
+                }
+                EditorTool::Partials => {
+                    if ui.button("Analyze partials").clicked() {
+                        self.analyze_partials();
+                    }
+                    if ui.button("Play partials").clicked() {
+                        self.play_partials();
+                    }
+                }
+            }
+        });
         egui::containers::ScrollArea::both()
             .scroll_source(ScrollSource::SCROLL_BAR | ScrollSource::MOUSE_WHEEL)
             .show(ui, |ui| {
@@ -193,10 +836,95 @@ impl MyEditor {
             self.layout_img = Some(egui::load::Bytes::Shared(buf.into()));
         }
 
+        self.audio_picker.update(ui.ctx());
+        if let Some(path) = self.audio_picker.take_picked() {
+            self.load_error = self.load_recording(path).err();
+        }
+
+        ui.horizontal(|ui| {
+            let filters = [
+                (
+                    "Blur",
+                    MagnitudeFilter::GaussianBlur {
+                        sigma_time: 1f32,
+                        sigma_freq: 1f32,
+                    },
+                ),
+                (
+                    "Sharpen",
+                    MagnitudeFilter::Sharpen {
+                        amount: 1f32,
+                        sigma_time: 1f32,
+                        sigma_freq: 1f32,
+                    },
+                ),
+                (
+                    "Median",
+                    MagnitudeFilter::Median {
+                        radius_time: 1,
+                        radius_freq: 1,
+                    },
+                ),
+            ];
+            for (label, filter) in filters {
+                if ui.button(label).clicked() {
+                    self.spectrogram
+                        .apply_filter(&filter, FilterPhase::Preserve);
+                    self.samples = None;
+                    self.sized_tx = None;
+                    self.reset_img();
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Window");
+            ui.add(egui::DragValue::new(&mut self.pending_settings.window_size).range(16..=65536));
+            // Analysis and synthesis need an even window.
+            self.pending_settings.window_size &= !1;
+            ui.label("+ padding");
+            ui.add(
+                egui::DragValue::new(&mut self.pending_settings.window_pad_amnt)
+                    .range(0..=8 * self.pending_settings.window_size),
+            );
+            if ui.button("Convert").clicked() && self.pending_settings != self.settings {
+                self.change_settings(self.pending_settings);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Sample rate");
+            for rate in [22050, 44100, 48000, 96000] {
+                if ui
+                    .selectable_label(self.sample_rate == rate, rate.to_string())
+                    .clicked()
+                    && self.sample_rate != rate
+                {
+                    self.change_sample_rate(rate);
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Columns");
+            ui.add(egui::DragValue::new(&mut self.pending_width).range(2..=100000));
+            for (label, interpolation) in [
+                ("Nearest", ResizeInterpolation::Nearest),
+                ("Linear", ResizeInterpolation::Linear),
+                ("Cubic", ResizeInterpolation::Cubic),
+            ] {
+                ui.radio_value(&mut self.resize_interpolation, interpolation, label);
+            }
+            if ui.button("Resize").clicked() && self.pending_width != self.width {
+                self.change_width(self.pending_width);
+            }
+        });
+
         if ui.button("Clear").clicked() {
             self.spectrogram.data = vec![Complex::ZERO; self.width * self.spectrogram.height];
             self.samples = None;
             self.sized_tx = None;
+            self.mask = None;
             self.primary_brush = Box::new(RadiusBrush::new(self.default_brightness, 1f32)); //Box::new(SolidMagBrush::new(self.default_brightness));
             self.reset_img();
         }
@@ -204,14 +932,10 @@ impl MyEditor {
 
     pub fn play(&mut self) {
         if self.samples.is_none() {
-            self.samples = Some(spectrogram::inverse::inverse_mt(
+            self.samples = Some(self.engine.synthesize(
                 &self.spectrogram,
-                &SpectrogramSettings {
-                    window_size: self.window_len,
-                    window_pad_amnt: 0,
-                },
+                &self.spectrogram_settings(),
                 4,
-                true,
             ));
         }
 
@@ -1,10 +1,17 @@
 use egui::Vec2;
 use rustfft::num_complex::Complex;
-use spectrogram::SpectrogramImage;
+use spectrogram::{SpectrogramImage, SpectrogramSettings};
 
 pub trait Brush {
     fn update_with_scroll(&mut self, delta: f32);
-    fn apply(&self, img: &mut SpectrogramImage, bin_range: [usize; 2], norm_pos: Vec2);
+    // `settings` describes the image's bins; brushes sized in bins mean unpadded ones.
+    fn apply(
+        &self,
+        img: &mut SpectrogramImage,
+        settings: &SpectrogramSettings,
+        bin_range: [usize; 2],
+        norm_pos: Vec2,
+    );
 }
 
 pub mod radius_brush;
@@ -1,6 +1,6 @@
 use egui::Vec2;
 use rustfft::num_complex::Complex;
-use spectrogram::SpectrogramImage;
+use spectrogram::{SpectrogramImage, SpectrogramSettings};
 
 use crate::app::editor_from_scratch::drawing::Brush;
 
@@ -21,7 +21,13 @@ impl Brush for RadiusBrush {
         self.brightness *= (delta / 20f32).exp();
     }
 
-    fn apply(&self, img: &mut SpectrogramImage, bin_range: [usize; 2], norm_pos: Vec2) {
+    fn apply(
+        &self,
+        img: &mut SpectrogramImage,
+        settings: &SpectrogramSettings,
+        bin_range: [usize; 2],
+        norm_pos: Vec2,
+    ) {
         let bin_diff = bin_range[1] - bin_range[0];
         let img_center = [
             (norm_pos.x * img.width as f32),
@@ -30,8 +36,11 @@ impl Brush for RadiusBrush {
         let rounded_center = [img_center[0] as usize, img_center[1] as usize];
         let rounded_rad = self.radius.ceil() as i32;
         let diam = rounded_rad * 2 + 1;
+        // Padding packs more bins into the same band, so the brush is taller in bins.
+        let stretch = settings.padding_factor();
+        let rounded_rad_y = (self.radius * stretch).ceil() as i32;
         for x in -rounded_rad..rounded_rad + 1 {
-            for y in -rounded_rad..rounded_rad + 1 {
+            for y in -rounded_rad_y..rounded_rad_y + 1 {
                 let translated_x = x + rounded_center[0] as i32;
                 let translated_y = y + rounded_center[1] as i32;
                 if translated_x >= 0
@@ -41,7 +50,7 @@ impl Brush for RadiusBrush {
                 {
                     let square = [translated_x as usize, translated_y as usize];
                     let dx = square[0] as f32 - img_center[0];
-                    let dy = square[1] as f32 - img_center[1];
+                    let dy = (square[1] as f32 - img_center[1]) / stretch;
                     let dist = (dx * dx + dy * dy); //.sqrt();
                     if dist <= self.radius * self.radius {
                         let bgt = self.brightness * (1f32 - dist / (self.radius * self.radius));
@@ -1,6 +1,6 @@
 use egui::Vec2;
 use rustfft::num_complex::Complex;
-use spectrogram::SpectrogramImage;
+use spectrogram::{SpectrogramImage, SpectrogramSettings};
 
 use crate::app::editor_from_scratch::drawing::Brush;
 
@@ -20,7 +20,13 @@ impl Brush for SolidMagBrush {
         self.brightness *= (delta / 20f32).exp();
     }
 
-    fn apply(&self, img: &mut SpectrogramImage, bin_range: [usize; 2], norm_pos: Vec2) {
+    fn apply(
+        &self,
+        img: &mut SpectrogramImage,
+        _settings: &SpectrogramSettings,
+        bin_range: [usize; 2],
+        norm_pos: Vec2,
+    ) {
         let bin_diff = bin_range[1] - bin_range[0];
         let img_coord = [
             (norm_pos.x * img.width as f32) as usize,
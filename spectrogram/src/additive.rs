@@ -0,0 +1,226 @@
+use crate::{
+    SpectrogramFloat, SpectrogramImage, SpectrogramSettings, inverse::inverse_mt,
+    sinusoidal::PeakTracking, wrap_phase,
+};
+
+// One sinusoid's trajectory, given at the centres of consecutive columns starting at
+// `first_column`. Frequencies are in (fractional) bins, amplitudes in sample units, and
+// `phase` is the phase at the first column's centre.
+#[derive(Clone, Debug)]
+pub struct Partial<F: SpectrogramFloat = f32> {
+    pub first_column: usize,
+    pub bins: Vec<F>,
+    pub amplitudes: Vec<F>,
+    pub phase: F,
+}
+
+impl<F: SpectrogramFloat> Partial<F> {
+    pub(crate) fn new(first_column: usize, bin: F, amplitude: F, phase: F) -> Self {
+        Self {
+            first_column,
+            bins: vec![bin],
+            amplitudes: vec![amplitude],
+            phase,
+        }
+    }
+}
+
+// Which oscillators an additive render drives.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OscillatorBank {
+    // One oscillator per bin, at the bin's centre frequency, for as long as the bin isn't
+    // silent. Fine for painted images; analyzed ones beat, since every sinusoid spans
+    // several bins.
+    EveryBin,
+    // One oscillator per spectral peak, at its interpolated frequency, following the peak
+    // from column to column. At most `max_count` peaks per column.
+    Peaks { max_count: usize },
+}
+
+// How a spectrogram is turned back into samples.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SynthesisEngine {
+    // Overlap-added inverse FFTs, as `inverse::inverse_mt`.
+    InverseFft { awful_hack: bool },
+    // A bank of sinusoidal oscillators with continuous phase. Only each partial's starting
+    // phase is taken from the image, so made-up phases don't matter.
+    Additive(OscillatorBank),
+}
+
+impl SynthesisEngine {
+    // The same engine, with `awful_hack` instead if it is the inverse FFT.
+    pub fn with_awful_hack(self, awful_hack: bool) -> Self {
+        match self {
+            Self::InverseFft { .. } => Self::InverseFft { awful_hack },
+            additive => additive,
+        }
+    }
+
+    // Same length and alignment as `inverse::inverse_mt`, whichever engine.
+    pub fn synthesize<F: SpectrogramFloat>(
+        self,
+        spectrogram: &SpectrogramImage<F>,
+        settings: &SpectrogramSettings,
+        thread_ct: usize,
+    ) -> Vec<F> {
+        match self {
+            Self::InverseFft { awful_hack } => {
+                inverse_mt(spectrogram, settings, thread_ct, awful_hack)
+            }
+            Self::Additive(bank) => {
+                let partials = spectrogram.partials(bank, settings);
+                render_partials(&partials, settings, spectrogram.width, thread_ct)
+            }
+        }
+    }
+}
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    // Amplitude of the sinusoid that a bin of magnitude `mag` at bin `y` stands for. DC and
+    // Nyquist don't share their energy with a negative-frequency twin.
+    pub(crate) fn oscillator_amplitude(
+        &self,
+        mag: F,
+        y: usize,
+        settings: &SpectrogramSettings,
+    ) -> F {
+        let amplitude = mag / F::lit(settings.full_scale_magnitude() as f64);
+        if y == 0 || y == self.height - 1 {
+            amplitude * F::lit(0.5)
+        } else {
+            amplitude
+        }
+    }
+
+    pub fn partials(
+        &self,
+        bank: OscillatorBank,
+        settings: &SpectrogramSettings,
+    ) -> Vec<Partial<F>> {
+        match bank {
+            OscillatorBank::EveryBin => self.bin_partials(settings),
+            OscillatorBank::Peaks { max_count } => self.track_partials(
+                settings,
+                &PeakTracking {
+                    max_count,
+                    floor_db: f32::NEG_INFINITY,
+                    min_columns: 1,
+                    instantaneous_frequency: false,
+                    ..Default::default()
+                },
+            ),
+        }
+    }
+
+    // A partial for every unbroken run of non-silent columns in every bin.
+    fn bin_partials(&self, settings: &SpectrogramSettings) -> Vec<Partial<F>> {
+        let mut finished = vec![];
+        let mut active: Vec<Option<Partial<F>>> = vec![None; self.height];
+        for x in 0..self.width {
+            for (y, slot) in active.iter_mut().enumerate() {
+                let bin = self.get_at(x, y);
+                let mag = bin.norm();
+                if mag.is_zero() {
+                    finished.extend(slot.take());
+                    continue;
+                }
+                let amplitude = self.oscillator_amplitude(mag, y, settings);
+                match slot {
+                    Some(partial) => {
+                        partial.bins.push(F::lit(y as f64));
+                        partial.amplitudes.push(amplitude);
+                    }
+                    None => *slot = Some(Partial::new(x, F::lit(y as f64), amplitude, bin.arg())),
+                }
+            }
+        }
+        finished.extend(active.into_iter().flatten());
+        finished
+    }
+
+    pub fn additive_synthesis(
+        &self,
+        bank: OscillatorBank,
+        settings: &SpectrogramSettings,
+        thread_ct: usize,
+    ) -> Vec<F> {
+        SynthesisEngine::Additive(bank).synthesize(self, settings, thread_ct)
+    }
+}
+
+// Adds one partial into `output`. Column `x`'s centre is sample `(x + 1) * hop`, as in
+// `inverse_mt`; amplitude and frequency are linear between centres, and each partial fades
+// in over the hop before its first column and out over the hop after its last.
+fn render_partial<F: SpectrogramFloat>(
+    partial: &Partial<F>,
+    settings: &SpectrogramSettings,
+    output: &mut [F],
+) {
+    let hop = settings.hop_size();
+    let radians_per_bin = F::TAU() / F::lit(settings.fft_len() as f64);
+    let step = F::lit(hop as f64).recip();
+
+    // Breakpoints at every column centre, plus silent ones a hop either side.
+    let last = partial.bins.len() - 1;
+    let mut points = Vec::with_capacity(partial.bins.len() + 2);
+    points.push((partial.bins[0], F::zero()));
+    points.extend(
+        partial
+            .bins
+            .iter()
+            .copied()
+            .zip(partial.amplitudes.iter().copied()),
+    );
+    points.push((partial.bins[last], F::zero()));
+
+    // The first breakpoint is one hop before the first column's centre.
+    let start = partial.first_column * hop;
+    let mut phase =
+        wrap_phase(partial.phase - partial.bins[0] * radians_per_bin * F::lit(hop as f64));
+    for (i, pair) in points.windows(2).enumerate() {
+        let [(bin_a, amp_a), (bin_b, amp_b)] = [pair[0], pair[1]];
+        let segment_start = start + i * hop;
+        for n in 0..hop {
+            let Some(out) = output.get_mut(segment_start + n) else {
+                return;
+            };
+            let t = F::lit(n as f64) * step;
+            *out += (amp_a + (amp_b - amp_a) * t) * phase.cos();
+            phase += (bin_a + (bin_b - bin_a) * t) * radians_per_bin;
+        }
+        phase = wrap_phase(phase);
+    }
+}
+
+// Renders partials onto `width` columns' worth of samples, splitting them across threads.
+pub fn render_partials<F: SpectrogramFloat>(
+    partials: &[Partial<F>],
+    settings: &SpectrogramSettings,
+    width: usize,
+    thread_ct: usize,
+) -> Vec<F> {
+    let len = settings.hop_size() * width + settings.hop_size();
+    let chunk = partials.len().div_ceil(thread_ct.max(1)).max(1);
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = partials
+            .chunks(chunk)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut output = vec![F::zero(); len];
+                    for partial in chunk {
+                        render_partial(partial, settings, &mut output);
+                    }
+                    output
+                })
+            })
+            .collect();
+
+        let mut output = vec![F::zero(); len];
+        for thread in threads {
+            for (o, s) in output.iter_mut().zip(thread.join().unwrap()) {
+                *o += s;
+            }
+        }
+        output
+    })
+}
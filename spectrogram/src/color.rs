@@ -0,0 +1,105 @@
+use std::f32::consts::TAU;
+
+use crate::{
+    SpectrogramFloat, SpectrogramImage, SpectrogramIntensityPlotSettings,
+    SpectrogramPhasePlotSettings, UThing, real_safe_polar,
+};
+
+// Fully saturated colour of `hue` in [0, 1) at brightness `value`.
+fn hsv_to_rgb(hue: f32, value: f32) -> [f32; 3] {
+    let h = hue.rem_euclid(1f32) * 6f32;
+    // A tiny negative hue rounds up to exactly 1, which is red again.
+    let h = if h >= 6f32 { 0f32 } else { h };
+    let f = h.fract();
+    let rising = value * f;
+    let falling = value * (1f32 - f);
+    match h as usize {
+        0 => [value, rising, 0f32],
+        1 => [falling, value, 0f32],
+        2 => [0f32, value, rising],
+        3 => [0f32, falling, value],
+        4 => [rising, 0f32, value],
+        _ => [value, 0f32, falling],
+    }
+}
+
+// Hue in [0, 1) and value of a colour. Saturation is ignored, so a pixel that was
+// desaturated by an external edit still keeps its hue; greys read as hue 0.
+fn rgb_to_hue_value([r, g, b]: [f32; 3]) -> (f32, f32) {
+    let max = r.max(g).max(b);
+    let chroma = max - r.min(g).min(b);
+    if chroma <= 0f32 {
+        return (0f32, max);
+    }
+    let sector = if max == r {
+        ((g - b) / chroma).rem_euclid(6f32)
+    } else if max == g {
+        (b - r) / chroma + 2f32
+    } else {
+        (r - g) / chroma + 4f32
+    };
+    (sector / 6f32, max)
+}
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    // RGB pixels (three bytes each) in the same layout as the grayscale plots: brightness
+    // is the intensity plot, hue is the absolute phase starting from `phase.lower_seam`.
+    // Hue resolution drops with brightness, so the phase of very quiet bins is coarse.
+    pub fn to_hsv_bytes(
+        &self,
+        intensity: &SpectrogramIntensityPlotSettings,
+        phase: &SpectrogramPhasePlotSettings,
+        buffer: &mut [u8],
+    ) {
+        assert_eq!(intensity.bin_range, phase.bin_range);
+        for x in 0..self.width {
+            for y in intensity.bin_range[0]..intensity.bin_range[1] {
+                let bin = self.get_at(x, y);
+                let value = intensity
+                    .magnitude_to_frac(bin.norm().as_f32())
+                    .clamp(0f32, 1f32);
+                let hue = Self::arg_seamed_at(bin, phase.lower_seam) / TAU;
+                let pixel = ((intensity.bin_range[1] - 1 - y) * self.width + x) * 3;
+                for (out, c) in buffer[pixel..pixel + 3]
+                    .iter_mut()
+                    .zip(hsv_to_rgb(hue, value))
+                {
+                    *out = u8::as_frac(c);
+                }
+            }
+        }
+    }
+
+    pub fn create_hsv_bytes(
+        &self,
+        intensity: &SpectrogramIntensityPlotSettings,
+        phase: &SpectrogramPhasePlotSettings,
+    ) -> Vec<u8> {
+        let rows = intensity.bin_range[1] - intensity.bin_range[0];
+        let mut bytes = vec![0u8; self.width * rows * 3];
+        self.to_hsv_bytes(intensity, phase, &mut bytes);
+        bytes
+    }
+
+    // Inverse of `to_hsv_bytes`: overwrites magnitude and phase of every bin in the range.
+    pub fn apply_hsv_bytes(
+        &mut self,
+        intensity: &SpectrogramIntensityPlotSettings,
+        phase: &SpectrogramPhasePlotSettings,
+        buffer: &[u8],
+    ) {
+        assert_eq!(intensity.bin_range, phase.bin_range);
+        let height = self.height;
+        for x in 0..self.width {
+            for y in intensity.bin_range[0]..intensity.bin_range[1] {
+                let pixel = ((intensity.bin_range[1] - 1 - y) * self.width + x) * 3;
+                let [r, g, b] = [buffer[pixel], buffer[pixel + 1], buffer[pixel + 2]];
+                let (hue, _) = rgb_to_hue_value([r.to_frac(), g.to_frac(), b.to_frac()]);
+                let magnitude = intensity.byte_to_magnitude(r.max(g).max(b));
+                let angle = hue * TAU + phase.lower_seam;
+                *self.mut_get_at(x, y) =
+                    real_safe_polar(F::lit(magnitude as f64), F::lit(angle as f64), y, height);
+            }
+        }
+    }
+}
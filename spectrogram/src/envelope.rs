@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use realfft::{ComplexToReal, RealToComplex};
+use rustfft::num_complex::Complex;
+
+use crate::SpectrogramFloat;
+
+// Smallest magnitude taken into the log domain; keeps silent bins finite.
+pub(crate) fn log_floor<F: SpectrogramFloat>() -> F {
+    F::lit(1e-6)
+}
+
+pub fn log_magnitudes<F: SpectrogramFloat>(column: &[Complex<F>]) -> Vec<F> {
+    column
+        .iter()
+        .map(|c| c.norm().max(log_floor()).ln())
+        .collect()
+}
+
+// As `log_magnitudes`, but floored 80 dB below the loudest bin so the near-silent gaps
+// between partials don't drag cepstral envelopes down.
+fn peak_floored_log_magnitudes<F: SpectrogramFloat>(column: &[Complex<F>]) -> Vec<F> {
+    let max_mag = column.iter().fold(F::zero(), |a, c| a.max(c.norm()));
+    let floor = (max_mag * F::lit(1e-4)).max(log_floor());
+    column.iter().map(|c| c.norm().max(floor).ln()).collect()
+}
+
+// Spectral envelope by cepstral liftering: the log spectrum is taken to the quefrency
+// domain, everything above `lifter` coefficients is dropped, and it is transformed back.
+// Spectra shorter than two bins have no cepstrum and pass through unsmoothed.
+pub struct CepstralSmoother<F: SpectrogramFloat = f32> {
+    to_cepstrum: Option<Arc<dyn ComplexToReal<F>>>,
+    from_cepstrum: Option<Arc<dyn RealToComplex<F>>>,
+    lifter: usize,
+}
+
+impl<F: SpectrogramFloat> CepstralSmoother<F> {
+    // `spectrum_size` is the column height the smoother will be used on.
+    pub fn new(spectrum_size: usize, lifter: usize) -> Self {
+        let cepstrum_len = 2 * spectrum_size.saturating_sub(1);
+        let mut planner = realfft::RealFftPlanner::new();
+        Self {
+            to_cepstrum: (spectrum_size >= 2).then(|| planner.plan_fft_inverse(cepstrum_len)),
+            from_cepstrum: (spectrum_size >= 2).then(|| planner.plan_fft_forward(cepstrum_len)),
+            lifter: lifter.max(1),
+        }
+    }
+
+    // Takes and returns natural-log magnitudes.
+    pub fn smooth_log(&self, log_mags: &[F]) -> Vec<F> {
+        let (Some(to_cepstrum), Some(from_cepstrum)) = (&self.to_cepstrum, &self.from_cepstrum)
+        else {
+            return log_mags.to_vec();
+        };
+        let mut spectrum: Vec<Complex<F>> = log_mags.iter().map(|&l| Complex::from(l)).collect();
+        let mut cepstrum = to_cepstrum.make_output_vec();
+        to_cepstrum.process(&mut spectrum, &mut cepstrum).unwrap();
+
+        let len = cepstrum.len();
+        if self.lifter < len / 2 {
+            cepstrum[self.lifter..=len - self.lifter].fill(F::zero());
+        }
+
+        let mut smoothed = from_cepstrum.make_output_vec();
+        from_cepstrum.process(&mut cepstrum, &mut smoothed).unwrap();
+        let len_recip = F::lit(len as f64).recip();
+        smoothed.iter().map(|c| c.re * len_recip).collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EnvelopeSmoothing {
+    // Keep this many cepstral coefficients; lower is smoother.
+    Cepstral { lifter: usize },
+    // Average into this many log-spaced bands, like a channel vocoder's filter bank.
+    Bands { count: usize },
+}
+
+// Log-spaced band edges over bins `1..spectrum_size`, each band at least one bin wide.
+fn band_edges(spectrum_size: usize, count: usize) -> Vec<usize> {
+    let last = spectrum_size.max(2) - 1;
+    let count = count.clamp(1, last);
+    let mut edges = vec![1usize];
+    for i in 1..count {
+        let remaining = count - i;
+        let ideal = ((last as f32).ln() * i as f32 / count as f32).exp().round() as usize;
+        let edge = ideal.max(edges[i - 1] + 1).min(last + 1 - remaining);
+        edges.push(edge);
+    }
+    edges.push(last + 1);
+    edges
+}
+
+pub struct EnvelopeExtractor<F: SpectrogramFloat = f32> {
+    smoothing: EnvelopeSmoothing,
+    cepstral: Option<CepstralSmoother<F>>,
+    band_edges: Vec<usize>,
+}
+
+impl<F: SpectrogramFloat> EnvelopeExtractor<F> {
+    pub fn new(spectrum_size: usize, smoothing: EnvelopeSmoothing) -> Self {
+        match smoothing {
+            EnvelopeSmoothing::Cepstral { lifter } => Self {
+                smoothing,
+                cepstral: Some(CepstralSmoother::new(spectrum_size, lifter)),
+                band_edges: vec![],
+            },
+            EnvelopeSmoothing::Bands { count } => Self {
+                smoothing,
+                cepstral: None,
+                band_edges: band_edges(spectrum_size, count),
+            },
+        }
+    }
+
+    // Natural-log envelope of one column.
+    pub fn log_envelope(&self, column: &[Complex<F>]) -> Vec<F> {
+        match self.smoothing {
+            EnvelopeSmoothing::Cepstral { .. } => self
+                .cepstral
+                .as_ref()
+                .unwrap()
+                .smooth_log(&peak_floored_log_magnitudes(column)),
+            EnvelopeSmoothing::Bands { .. } => self.band_log_envelope(column),
+        }
+    }
+
+    // RMS level of each band, placed at the band centre and interpolated linearly
+    // (in the log domain) between centres.
+    fn band_log_envelope(&self, column: &[Complex<F>]) -> Vec<F> {
+        let mut centres = vec![];
+        let mut levels = vec![];
+        for band in self.band_edges.windows(2) {
+            let bins = &column[band[0]..band[1]];
+            let power = bins.iter().fold(F::zero(), |acc, c| acc + c.norm_sqr());
+            let rms = (power / F::lit(bins.len() as f64)).sqrt();
+            centres.push((band[0] + band[1] - 1) as f32 / 2f32);
+            levels.push(rms.max(log_floor()).ln());
+        }
+
+        (0..column.len())
+            .map(|y| {
+                let y = y as f32;
+                let seg = centres.partition_point(|&c| c <= y);
+                if seg == 0 {
+                    levels[0]
+                } else if seg == centres.len() {
+                    levels[seg - 1]
+                } else {
+                    let t = (y - centres[seg - 1]) / (centres[seg] - centres[seg - 1]);
+                    levels[seg - 1] + (levels[seg] - levels[seg - 1]) * F::lit(t as f64)
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{SpectrogramFloat, SpectrogramImage, SpectrogramSettings};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CurveInterpolation {
+    // Straight lines between breakpoints on a linear frequency axis.
+    Linear,
+    // Straight lines on a log frequency axis, like a graphic EQ.
+    Log,
+}
+
+// Gain in dB over frequency, as `[hz, db]` breakpoints sorted by frequency. Below the
+// first and above the last breakpoint the gain is held flat.
+#[derive(Clone, Debug)]
+pub struct GainCurve {
+    pub points: Vec<[f32; 2]>,
+    pub interpolation: CurveInterpolation,
+}
+
+impl GainCurve {
+    pub fn flat() -> Self {
+        Self {
+            points: vec![],
+            interpolation: CurveInterpolation::Log,
+        }
+    }
+
+    pub fn db_at(&self, hz: f32) -> f32 {
+        let Some(first) = self.points.first() else {
+            return 0f32;
+        };
+        if hz <= first[0] {
+            return first[1];
+        }
+        let axis = |f: f32| match self.interpolation {
+            CurveInterpolation::Linear => f,
+            // Clamped so a breakpoint at 0 Hz doesn't send everything to -inf.
+            CurveInterpolation::Log => f.max(1f32).ln(),
+        };
+        for pair in self.points.windows(2) {
+            let [a, b] = [pair[0], pair[1]];
+            if hz <= b[0] {
+                let t = (axis(hz) - axis(a[0])) / (axis(b[0]) - axis(a[0])).max(f32::EPSILON);
+                return a[1] + (b[1] - a[1]) * t.clamp(0f32, 1f32);
+            }
+        }
+        self.points.last().unwrap()[1]
+    }
+
+    // dB gain of every bin of a spectrum.
+    pub fn bin_db(
+        &self,
+        height: usize,
+        settings: &SpectrogramSettings,
+        sample_rate: u32,
+    ) -> Vec<f32> {
+        (0..height)
+            .map(|y| self.db_at(settings.bin_frequency(y, sample_rate)))
+            .collect()
+    }
+}
+
+// A gain curve that changes over time. Keyframe positions are fractions of the
+// spectrogram width in [0, 1]; between keyframes the dB gains are crossfaded bin by bin.
+#[derive(Clone, Debug)]
+pub struct EqCurve {
+    pub keyframes: Vec<(f32, GainCurve)>,
+}
+
+impl EqCurve {
+    pub fn constant(curve: GainCurve) -> Self {
+        Self {
+            keyframes: vec![(0f32, curve)],
+        }
+    }
+}
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    // Multiplies every bin by the gain of `curve` at its frequency and time. Phases are
+    // left alone.
+    pub fn apply_eq(&mut self, curve: &EqCurve, settings: &SpectrogramSettings, sample_rate: u32) {
+        if curve.keyframes.is_empty() {
+            return;
+        }
+        let positions: Vec<f32> = curve.keyframes.iter().map(|(p, _)| *p).collect();
+        let gains: Vec<Vec<f32>> = curve
+            .keyframes
+            .iter()
+            .map(|(_, c)| c.bin_db(self.height, settings, sample_rate))
+            .collect();
+
+        let width = self.width;
+        for x in 0..width {
+            let position = x as f32 / (width.max(2) - 1) as f32;
+            let next = positions.partition_point(|&p| p <= position);
+            let (lo, hi, t) = if next == 0 {
+                (0, 0, 0f32)
+            } else if next == positions.len() {
+                (next - 1, next - 1, 0f32)
+            } else {
+                let span = (positions[next] - positions[next - 1]).max(f32::EPSILON);
+                (next - 1, next, (position - positions[next - 1]) / span)
+            };
+            for (y, bin) in self.column_mut(x).iter_mut().enumerate() {
+                let db = gains[lo][y] + (gains[hi][y] - gains[lo][y]) * t;
+                *bin *= F::lit(10f64.powf(db as f64 / 20f64));
+            }
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{
+    SpectrogramFloat, SpectrogramImage, SpectrogramSettings, noise::RandomPhases,
+    ops::with_magnitude,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ThresholdMode {
+    // Bins below the level are silenced, the rest are untouched.
+    Hard,
+    // Every bin is pulled down by the level, flooring at silence.
+    Soft,
+}
+
+// Image-style filters over magnitudes. Sigmas and radii are in columns (time) and bins
+// (frequency) and can be set independently; zero leaves that axis alone.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MagnitudeFilter {
+    GaussianBlur {
+        sigma_time: f32,
+        sigma_freq: f32,
+    },
+    // Unsharp mask: adds back `amount` times the difference from a Gaussian blur.
+    Sharpen {
+        amount: f32,
+        sigma_time: f32,
+        sigma_freq: f32,
+    },
+    // `level_db` is relative to the loudest bin, e.g. -40.
+    Threshold {
+        level_db: f32,
+        mode: ThresholdMode,
+    },
+    Dilate {
+        radius_time: usize,
+        radius_freq: usize,
+    },
+    Erode {
+        radius_time: usize,
+        radius_freq: usize,
+    },
+    Median {
+        radius_time: usize,
+        radius_freq: usize,
+    },
+}
+
+#[derive(Clone, Copy)]
+pub enum FilterPhase {
+    // Keep each bin's phase and only rescale it.
+    Preserve,
+    // Phases (and for the Gaussian distributions, magnitude scatter) from
+    // `SpectrogramImage::apply_noise`.
+    Random(RandomPhases),
+    // Regenerate with `SpectrogramImage::apply_peak_locked_phases`.
+    PeakLocked(SpectrogramSettings),
+}
+
+// Normalized Gaussian weights out to three sigmas.
+pub(crate) fn gaussian_kernel(sigma: f32) -> Vec<f32> {
+    let half = (sigma * 3f32).ceil().max(0f32) as i32;
+    let weights: Vec<f32> = (-half..=half)
+        .map(|i| (-0.5f32 * (i as f32 / sigma.max(f32::EPSILON)).powi(2)).exp())
+        .collect();
+    let total: f32 = weights.iter().sum();
+    weights.iter().map(|w| w / total).collect()
+}
+
+// Magnitudes laid out like `SpectrogramImage::data`, with clamped neighbourhood access.
+struct MagnitudePlane<F: SpectrogramFloat> {
+    width: usize,
+    height: usize,
+    mags: Vec<F>,
+}
+
+impl<F: SpectrogramFloat> MagnitudePlane<F> {
+    fn at(&self, x: isize, y: isize) -> F {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.mags[x * self.height + y]
+    }
+
+    fn map(&self, f: impl Fn(isize, isize) -> F) -> Vec<F> {
+        let mut out = Vec::with_capacity(self.mags.len());
+        for x in 0..self.width as isize {
+            for y in 0..self.height as isize {
+                out.push(f(x, y));
+            }
+        }
+        out
+    }
+
+    fn convolve_axis(&self, kernel: &[f32], along_time: bool) -> Self {
+        let half = (kernel.len() / 2) as isize;
+        let mags = self.map(|x, y| {
+            kernel.iter().enumerate().fold(F::zero(), |acc, (k, w)| {
+                let offset = k as isize - half;
+                let v = if along_time {
+                    self.at(x + offset, y)
+                } else {
+                    self.at(x, y + offset)
+                };
+                acc + v * F::lit(*w as f64)
+            })
+        });
+        Self {
+            width: self.width,
+            height: self.height,
+            mags,
+        }
+    }
+
+    fn gaussian_blur(&self, sigma_time: f32, sigma_freq: f32) -> Vec<F> {
+        let blurred_time = self.convolve_axis(&gaussian_kernel(sigma_time), true);
+        blurred_time
+            .convolve_axis(&gaussian_kernel(sigma_freq), false)
+            .mags
+    }
+
+    fn neighbourhood(&self, x: isize, y: isize, radius_time: usize, radius_freq: usize) -> Vec<F> {
+        let (rt, rf) = (radius_time as isize, radius_freq as isize);
+        let mut values = Vec::with_capacity(((2 * rt + 1) * (2 * rf + 1)) as usize);
+        for dx in -rt..=rt {
+            for dy in -rf..=rf {
+                values.push(self.at(x + dx, y + dy));
+            }
+        }
+        values
+    }
+
+    fn apply(&self, filter: &MagnitudeFilter) -> Vec<F> {
+        match *filter {
+            MagnitudeFilter::GaussianBlur {
+                sigma_time,
+                sigma_freq,
+            } => self.gaussian_blur(sigma_time, sigma_freq),
+            MagnitudeFilter::Sharpen {
+                amount,
+                sigma_time,
+                sigma_freq,
+            } => {
+                let amount = F::lit(amount as f64);
+                let blurred = self.gaussian_blur(sigma_time, sigma_freq);
+                self.mags
+                    .iter()
+                    .zip(blurred)
+                    .map(|(&m, b)| (m + (m - b) * amount).max(F::zero()))
+                    .collect()
+            }
+            MagnitudeFilter::Threshold { level_db, mode } => {
+                let max_mag = self.mags.iter().fold(F::zero(), |a, &b| a.max(b));
+                let level = max_mag * F::lit(10f64.powf(level_db as f64 / 20.0));
+                self.mags
+                    .iter()
+                    .map(|&m| match mode {
+                        ThresholdMode::Hard if m < level => F::zero(),
+                        ThresholdMode::Hard => m,
+                        ThresholdMode::Soft => (m - level).max(F::zero()),
+                    })
+                    .collect()
+            }
+            MagnitudeFilter::Dilate {
+                radius_time,
+                radius_freq,
+            } => self.map(|x, y| {
+                self.neighbourhood(x, y, radius_time, radius_freq)
+                    .into_iter()
+                    .fold(F::zero(), F::max)
+            }),
+            MagnitudeFilter::Erode {
+                radius_time,
+                radius_freq,
+            } => self.map(|x, y| {
+                self.neighbourhood(x, y, radius_time, radius_freq)
+                    .into_iter()
+                    .fold(F::infinity(), F::min)
+            }),
+            MagnitudeFilter::Median {
+                radius_time,
+                radius_freq,
+            } => self.map(|x, y| {
+                let mut values = self.neighbourhood(x, y, radius_time, radius_freq);
+                let mid = values.len() / 2;
+                *values
+                    .select_nth_unstable_by(mid, |a, b| a.partial_cmp(b).unwrap())
+                    .1
+            }),
+        }
+    }
+}
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    pub fn apply_filter(&mut self, filter: &MagnitudeFilter, phase: FilterPhase) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let plane = MagnitudePlane {
+            width: self.width,
+            height: self.height,
+            mags: self.data.iter().map(|c| c.norm()).collect(),
+        };
+        let filtered = plane.apply(filter);
+
+        for (c, (old, new)) in self.data.iter_mut().zip(plane.mags.iter().zip(filtered)) {
+            *c = with_magnitude(*c, *old, new);
+        }
+
+        match phase {
+            FilterPhase::Preserve => {}
+            FilterPhase::Random(noise) => self.apply_noise(&noise),
+            FilterPhase::PeakLocked(settings) => self.apply_peak_locked_phases(&settings),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{f32::consts::TAU, sync::Arc, thread};
+use std::{sync::Arc, thread};
 
 use image::{ImageBuffer, Luma, Primitive};
 use realfft::RealToComplex;
@@ -7,11 +7,11 @@ use rustfft::{
     num_complex::{Complex, Complex32},
 };
 
-use crate::{SpectrogramImage, SpectrogramSettings, UThing};
+use crate::{SpectrogramFloat, SpectrogramImage, SpectrogramSettings, UThing};
 
 // https://en.wikipedia.org/wiki/Hann_function
-fn hann(n: usize, recip_len: f32) -> f32 {
-    0.5f32 * (1f32 - (TAU * n as f32 * recip_len).cos())
+fn hann<F: SpectrogramFloat>(n: usize, recip_len: F) -> F {
+    F::lit(0.5) * (F::one() - (F::TAU() * F::lit(n as f64) * recip_len).cos())
 }
 
 fn analyze_with_hann_window(fft: &Arc<dyn Fft<f32>>, query: &[f32]) -> Vec<Complex32> {
@@ -25,29 +25,29 @@ fn analyze_with_hann_window(fft: &Arc<dyn Fft<f32>>, query: &[f32]) -> Vec<Compl
     inputs
 }
 
-fn analyze_shifted_real_with_hann_window(
-    fft: &Arc<dyn RealToComplex<f32>>,
-    query: &[f32],
+pub(crate) fn analyze_shifted_real_with_hann_window<F: SpectrogramFloat>(
+    fft: &Arc<dyn RealToComplex<F>>,
+    query: &[F],
     pad_by: usize,
-) -> Vec<Complex32> {
-    let recip_len = ((query.len() - 1) as f32).recip();
-    let mut inputs: Vec<f32> = query
+) -> Vec<Complex<F>> {
+    let recip_len = F::lit((query.len() - 1) as f64).recip();
+    let mut inputs: Vec<F> = query
         .into_iter()
         .enumerate()
-        .map(|(i, f)| f * hann(i, recip_len))
+        .map(|(i, f)| *f * hann(i, recip_len))
         .collect();
-    inputs.resize(query.len() + pad_by, 0f32);
+    inputs.resize(query.len() + pad_by, F::zero());
     inputs.rotate_left(query.len() / 2);
     let mut outputs = fft.make_output_vec();
     fft.process(&mut inputs, &mut outputs).unwrap();
     outputs
 }
 
-pub fn analyze_mt(
-    query: &Vec<f32>,
+pub fn analyze_mt<F: SpectrogramFloat>(
+    query: &Vec<F>,
     settings: &SpectrogramSettings,
     thread_ct: usize,
-) -> Option<SpectrogramImage> {
+) -> Option<SpectrogramImage<F>> {
     let window_size = settings.window_size;
     if window_size % 2 == 1 {
         panic!()
@@ -59,12 +59,12 @@ pub fn analyze_mt(
 
     let not_fit_in_window = query.len() % window_size;
     let to_pad_by = window_size + window_size - not_fit_in_window;
-    let to_pad_by_on_left = to_pad_by / 2;
+    let to_pad_by_on_left = settings.analysis_offset(query.len());
     let to_pad_by_on_right = to_pad_by - to_pad_by_on_left;
 
-    let padded: Vec<f32> = std::iter::repeat_n(0f32, to_pad_by_on_left)
+    let padded: Vec<F> = std::iter::repeat_n(F::zero(), to_pad_by_on_left)
         .chain(query.iter().cloned())
-        .chain(std::iter::repeat_n(0f32, to_pad_by_on_right))
+        .chain(std::iter::repeat_n(F::zero(), to_pad_by_on_right))
         .collect();
 
     let padded_ref = Arc::new(padded);
@@ -111,7 +111,7 @@ pub fn analyze_mt(
 
                 let analyzed = analyze_shifted_real_with_hann_window(&cloned_fft, seg, pad_amnt);
                 assert_eq!(analyzed.len(), spectrum_size);
-                let mags: Vec<_> = analyzed.into_iter().map(|f| f * 2f32).collect();
+                let mags: Vec<_> = analyzed.into_iter().map(|f| f * F::lit(2.0)).collect();
 
                 let x = i + global_segment_start;
                 cloned_arc.send((x, mags)).unwrap();
@@ -0,0 +1,192 @@
+use std::ops::Range;
+
+use rand_chacha::ChaCha8Rng;
+use rustfft::num_complex::Complex;
+
+use crate::{
+    SpectrogramFloat, SpectrogramImage, SpectrogramRegion, SpectrogramSettings,
+    filter::gaussian_kernel,
+    noise::{RandomPhases, random_phase},
+    real_safe_polar, wrap_phase,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FreezeContinuation {
+    // Fresh random phases every column: a diffuse, breathy sustain. Reproducible when
+    // seeded.
+    RandomPhase { seed: Option<u64> },
+    // Every bin keeps advancing at the rate measured going into the last source column
+    // (its centre frequency, at the very first column): a tonal sustain.
+    PhaseAdvance,
+}
+
+// Per-bin phase advance from column `x - 1` to `x`: the bin-centre advance corrected by
+// the measured phase deviation.
+fn measured_advances<F: SpectrogramFloat>(
+    img: &SpectrogramImage<F>,
+    x: usize,
+    settings: &SpectrogramSettings,
+) -> Vec<F> {
+    let per_bin: F = settings.phase_advance_per_bin();
+    (0..img.height)
+        .map(|y| {
+            let expected = per_bin * F::lit(y as f64);
+            if x == 0 {
+                return expected;
+            }
+            let delta = img.get_at(x, y).arg() - img.get_at(x - 1, y).arg();
+            expected + wrap_phase(delta - expected)
+        })
+        .collect()
+}
+
+// An endless continuation of the spectrum held across some source columns.
+pub struct SpectralFreeze<F: SpectrogramFloat = f32> {
+    magnitudes: Vec<F>,
+    phases: Vec<F>,
+    advances: Vec<F>,
+    continuation: FreezeContinuation,
+    rng: ChaCha8Rng,
+}
+
+impl<F: SpectrogramFloat> SpectralFreeze<F> {
+    // Holds the mean magnitude of `source` columns. Phases carry on from the last one.
+    pub fn new(
+        img: &SpectrogramImage<F>,
+        source: Range<usize>,
+        continuation: FreezeContinuation,
+        settings: &SpectrogramSettings,
+    ) -> Self {
+        assert!(!source.is_empty() && source.end <= img.width);
+        let count = F::lit(source.len() as f64);
+        let magnitudes = (0..img.height)
+            .map(|y| {
+                source
+                    .clone()
+                    .map(|x| img.get_at(x, y).norm())
+                    .fold(F::zero(), |a, b| a + b)
+                    / count
+            })
+            .collect();
+        let last = source.end - 1;
+        let phases = img.column(last).iter().map(|c| c.arg()).collect();
+        let advances = measured_advances(img, last, settings);
+        let seed = match continuation {
+            FreezeContinuation::RandomPhase { seed } => seed,
+            FreezeContinuation::PhaseAdvance => None,
+        };
+
+        Self {
+            rng: RandomPhases {
+                seed,
+                ..Default::default()
+            }
+            .rng(),
+            magnitudes,
+            phases,
+            advances,
+            continuation,
+        }
+    }
+}
+
+impl<F: SpectrogramFloat> Iterator for SpectralFreeze<F> {
+    type Item = Vec<Complex<F>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let height = self.magnitudes.len();
+        for (phase, advance) in self.phases.iter_mut().zip(&self.advances) {
+            *phase = match self.continuation {
+                FreezeContinuation::RandomPhase { .. } => random_phase(&mut self.rng),
+                FreezeContinuation::PhaseAdvance => wrap_phase(*phase + *advance),
+            };
+        }
+        Some(
+            (0..height)
+                .map(|y| real_safe_polar(self.magnitudes[y], self.phases[y], y, height))
+                .collect(),
+        )
+    }
+}
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    // Overwrites `target` with a sustained copy of the `source` columns.
+    pub fn freeze(
+        &mut self,
+        source: Range<usize>,
+        target: &SpectrogramRegion,
+        continuation: FreezeContinuation,
+        settings: &SpectrogramSettings,
+    ) {
+        let frozen = SpectralFreeze::new(self, source, continuation, settings);
+        let bins = target.bins.start..target.bins.end.min(self.height);
+        for (x, column) in target.columns.clone().zip(frozen) {
+            if x >= self.width {
+                break;
+            }
+            self.column_mut(x)[bins.clone()].copy_from_slice(&column[bins.clone()]);
+        }
+    }
+
+    // Blurs magnitudes along time with a Gaussian of `radius` columns. The per-bin phase
+    // advance is blurred alongside (weighted by magnitude) and re-integrated from the start
+    // of the region, so smeared partials stay phase coherent.
+    pub fn smear(
+        &mut self,
+        region: &SpectrogramRegion,
+        radius: f32,
+        settings: &SpectrogramSettings,
+    ) {
+        let columns = region.columns.start..region.columns.end.min(self.width);
+        let bins = region.bins.start..region.bins.end.min(self.height);
+        if columns.is_empty() || bins.is_empty() {
+            return;
+        }
+        let kernel = gaussian_kernel(radius);
+        let half = (kernel.len() / 2) as isize;
+        let per_bin: F = settings.phase_advance_per_bin();
+
+        let advances: Vec<Vec<F>> = (0..self.width)
+            .map(|x| measured_advances(self, x, settings))
+            .collect();
+
+        let mut smeared = vec![];
+        for x in columns.clone() {
+            let mut column = vec![];
+            for y in bins.clone() {
+                let expected = per_bin * F::lit(y as f64);
+                let mut mag = F::zero();
+                let mut weighted_deviation = F::zero();
+                for (k, w) in kernel.iter().enumerate() {
+                    let src_x = (x as isize + k as isize - half).clamp(0, self.width as isize - 1);
+                    let src_x = src_x as usize;
+                    let m = self.get_at(src_x, y).norm() * F::lit(*w as f64);
+                    mag += m;
+                    weighted_deviation += m * (advances[src_x][y] - expected);
+                }
+                let deviation = if mag > F::zero() {
+                    weighted_deviation / mag
+                } else {
+                    F::zero()
+                };
+                column.push((mag, expected + deviation));
+            }
+            smeared.push(column);
+        }
+
+        let height = self.height;
+        let mut phases: Vec<F> = bins
+            .clone()
+            .map(|y| self.get_at(columns.start, y).arg())
+            .collect();
+        for (i, x) in columns.enumerate() {
+            for (j, y) in bins.clone().enumerate() {
+                let (mag, advance) = smeared[i][j];
+                if i > 0 {
+                    phases[j] = wrap_phase(phases[j] + advance);
+                }
+                *self.mut_get_at(x, y) = real_safe_polar(mag, phases[j], y, height);
+            }
+        }
+    }
+}
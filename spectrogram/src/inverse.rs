@@ -1,21 +1,21 @@
 use std::sync::Arc;
 
 use realfft::ComplexToReal;
-use rustfft::num_complex::{Complex, Complex32};
+use rustfft::num_complex::Complex;
 
-use crate::{SpectrogramImage, SpectrogramSettings};
+use crate::{SpectrogramFloat, SpectrogramImage, SpectrogramSettings};
 
 use std::sync::mpsc;
 
-fn undo_to_real_no_changes(
-    fft: &Arc<dyn ComplexToReal<f32>>,
-    query: &mut [Complex32],
+pub(crate) fn undo_to_real_no_changes<F: SpectrogramFloat>(
+    fft: &Arc<dyn ComplexToReal<F>>,
+    query: &mut [Complex<F>],
     pad_amnt: usize,
     awful_hack: bool,
-) -> Vec<f32> {
+) -> Vec<F> {
     let mut outputs = fft.make_output_vec();
 
-    if awful_hack && (query[0].im != 0f32 || query[query.len() - 1].im != 0f32) {
+    if awful_hack && (!query[0].im.is_zero() || !query[query.len() - 1].im.is_zero()) {
         query[0] = Complex::ZERO;
         query[query.len() - 1] = Complex::ZERO;
     }
@@ -23,16 +23,16 @@ fn undo_to_real_no_changes(
     fft.process(query, &mut outputs).unwrap();
     let halflen = (outputs.len() - pad_amnt) / 2;
     outputs.rotate_right(halflen);
-    outputs.resize(outputs.len() - pad_amnt, 0f32);
+    outputs.resize(outputs.len() - pad_amnt, F::zero());
     outputs
 }
 
-pub fn inverse_mt(
-    spectrogram: &SpectrogramImage,
+pub fn inverse_mt<F: SpectrogramFloat>(
+    spectrogram: &SpectrogramImage<F>,
     settings: &SpectrogramSettings,
     thread_ct: usize,
     awful_hack: bool,
-) -> Vec<f32> {
+) -> Vec<F> {
     let window_size = settings.window_size;
     let pad_amnt = settings.window_pad_amnt;
 
@@ -53,7 +53,7 @@ pub fn inverse_mt(
     let img_width = spectrogram.width;
 
     let mut output_samples = Vec::new();
-    output_samples.resize(total_sample_count, 0f32);
+    output_samples.resize(total_sample_count, F::zero());
 
     println!("Beginning ifft");
 
@@ -109,10 +109,10 @@ pub fn inverse_mt(
 
     println!("Ifft done");
 
-    let len_recip = ((window_size + pad_amnt) as f32).recip();
+    let len_recip = F::lit((window_size + pad_amnt) as f64).recip();
 
     for val in &mut output_samples {
-        *val *= len_recip / 2f32;
+        *val *= len_recip / F::lit(2.0);
     }
 
     println!("Normalization done");
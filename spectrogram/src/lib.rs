@@ -1,25 +1,46 @@
-use std::{
-    f32::consts::{PI, TAU},
-    sync::Arc,
-    thread,
-};
+use std::{f32::consts::TAU, ops::Range, sync::Arc, thread};
 
 use image::{EncodableLayout, ImageBuffer, Luma, Primitive, Rgb};
 use realfft::{ComplexToReal, RealToComplex};
 use rodio::Sample;
 use rustfft::{
-    Fft,
-    num_complex::{Complex, Complex32},
-    num_traits::ConstZero,
+    Fft, FftNum,
+    num_complex::Complex,
+    num_traits::{ConstZero, Float, FloatConst, NumAssign},
 };
 
+// The sample and bin type the engine runs on. `f32` is the default everywhere;
+// `f64` is there for long accumulations (phase integration, iterative reconstruction).
+pub trait SpectrogramFloat: FftNum + Float + FloatConst + NumAssign + ConstZero + Default {
+    fn lit(v: f64) -> Self;
+    fn as_f32(self) -> f32;
+}
+impl SpectrogramFloat for f32 {
+    fn lit(v: f64) -> Self {
+        v as f32
+    }
+
+    fn as_f32(self) -> f32 {
+        self
+    }
+}
+impl SpectrogramFloat for f64 {
+    fn lit(v: f64) -> Self {
+        v
+    }
+
+    fn as_f32(self) -> f32 {
+        self as f32
+    }
+}
+
 pub trait UThing {
     fn as_frac(v: f32) -> Self;
     fn to_frac(self) -> f32;
 }
 impl UThing for u8 {
     fn as_frac(v: f32) -> Self {
-        (v * u8::MAX as f32) as u8
+        (v * u8::MAX as f32).round() as u8
     }
 
     fn to_frac(self) -> f32 {
@@ -28,7 +49,7 @@ impl UThing for u8 {
 }
 impl UThing for u16 {
     fn as_frac(v: f32) -> Self {
-        (v * u16::MAX as f32) as u16
+        (v * u16::MAX as f32).round() as u16
     }
 
     fn to_frac(self) -> f32 {
@@ -36,51 +57,224 @@ impl UThing for u16 {
     }
 }
 
-pub struct SpectrogramImage {
+// `data` is stored column-major: each column (one spectrum) is a contiguous run of
+// `height` bins, so FFT input/output is a plain slice copy. Go through `get_index`
+// rather than computing offsets by hand.
+#[derive(Clone)]
+pub struct SpectrogramImage<F: SpectrogramFloat = f32> {
     pub width: usize,
     pub height: usize,
-    pub data: Vec<Complex32>,
+    pub data: Vec<Complex<F>>,
 }
 
-#[derive(Clone, Copy)]
+pub type SpectrogramImage64 = SpectrogramImage<f64>;
+
+#[derive(Clone, Copy, PartialEq)]
 pub struct SpectrogramSettings {
     pub window_size: usize,
     pub window_pad_amnt: usize,
 }
 
+impl SpectrogramSettings {
+    pub fn hop_size(&self) -> usize {
+        self.window_size / 2
+    }
+
+    pub fn fft_len(&self) -> usize {
+        self.window_size + self.window_pad_amnt
+    }
+
+    // Height of every column.
+    pub fn bin_count(&self) -> usize {
+        self.fft_len() / 2 + 1
+    }
+
+    // Bins per bin of the unpadded window: how much finer zero padding samples frequency.
+    pub fn padding_factor(&self) -> f32 {
+        self.fft_len() as f32 / self.window_size as f32
+    }
+
+    // Phase a stationary sinusoid centred on bin 1 gains over one hop.
+    pub fn phase_advance_per_bin<F: SpectrogramFloat>(&self) -> F {
+        F::TAU() * F::lit(self.hop_size() as f64 / self.fft_len() as f64)
+    }
+
+    // Centre frequency of bin `y` in Hz.
+    pub fn bin_frequency(&self, y: usize, sample_rate: u32) -> f32 {
+        y as f32 * sample_rate as f32 / self.fft_len() as f32
+    }
+
+    // Fractional bin at `hz`; inverse of `bin_frequency`.
+    pub fn frequency_bin(&self, hz: f32, sample_rate: u32) -> f32 {
+        hz * self.fft_len() as f32 / sample_rate as f32
+    }
+
+    // Zeros `forward::analyze_mt` puts in front of a `len`-sample input: column `x` is
+    // centred on input sample `(x + 1) * hop_size() - analysis_offset(len)`.
+    pub fn analysis_offset(&self, len: usize) -> usize {
+        (2 * self.window_size - len % self.window_size) / 2
+    }
+
+    // Time in seconds of the centre of column `x` in `inverse::inverse_mt`'s output.
+    pub fn column_time(&self, x: usize, sample_rate: u32) -> f32 {
+        ((x + 1) * self.hop_size()) as f32 / sample_rate as f32
+    }
+
+    // Magnitude a full-scale sinusoid (amplitude 1) reaches at its bin: half the amplitude
+    // lands in the positive-frequency bin, weighted by the Hann window's sum of (N - 1) / 2,
+    // and columns are doubled on the way out of `forward`.
+    pub fn full_scale_magnitude(&self) -> f32 {
+        (self.window_size - 1) as f32 / 2f32
+    }
+}
+
+// A rectangle of columns (time) by bins (frequency).
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpectrogramRegion {
+    pub columns: Range<usize>,
+    pub bins: Range<usize>,
+}
+
+// Frequency a bin's phase is advanced at by `SpectrogramImage::apply_sinusoidal_phases`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SinusoidFrequency {
+    // The bin's own centre frequency. Exact for lines one bin tall; wider ones beat.
+    BinCentre,
+    // The interpolated frequency of the spectral peak whose region the bin falls in, so
+    // every bin under one peak moves together.
+    Instantaneous,
+}
+
+// How bin magnitudes map onto the axis `intensity_range` is measured in.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum IntensityScale {
+    // Natural log of the magnitude.
+    NaturalLog,
+    // Decibels relative to `reference`; see `IntensityScale::dbfs`.
+    Decibels { reference: f32 },
+    Linear,
+    Power,
+    // Magnitude to the power `1 / gamma`: above 1 lifts quiet bins, below 1 darkens them.
+    Gamma { gamma: f32 },
+}
+
+impl IntensityScale {
+    // Decibels relative to a full-scale sinusoid.
+    pub fn dbfs(settings: &SpectrogramSettings) -> Self {
+        Self::Decibels {
+            reference: settings.full_scale_magnitude(),
+        }
+    }
+
+    pub fn to_scale(self, magnitude: f32) -> f32 {
+        match self {
+            Self::NaturalLog => magnitude.ln(),
+            Self::Decibels { reference } => 20f32 * (magnitude / reference).log10(),
+            Self::Linear => magnitude,
+            Self::Power => magnitude * magnitude,
+            Self::Gamma { gamma } => magnitude.powf(gamma.recip()),
+        }
+    }
+
+    // Inverse of `to_scale`. Values below what any magnitude maps to give 0.
+    pub fn from_scale(self, value: f32) -> f32 {
+        match self {
+            Self::NaturalLog => value.exp(),
+            Self::Decibels { reference } => reference * 10f32.powf(value / 20f32),
+            Self::Linear => value.max(0f32),
+            Self::Power => value.max(0f32).sqrt(),
+            Self::Gamma { gamma } => value.max(0f32).powf(gamma),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct SpectrogramIntensityPlotSettings {
     pub bin_range: [usize; 2],
+    // Values of `scale` mapped to the darkest and brightest pixel.
     pub intensity_range: [f32; 2],
+    pub scale: IntensityScale,
+}
+
+impl SpectrogramIntensityPlotSettings {
+    // Where `magnitude` falls in `intensity_range`, 0 at the bottom and 1 at the top.
+    fn magnitude_to_frac(&self, magnitude: f32) -> f32 {
+        let range = self.intensity_range[1] - self.intensity_range[0];
+        (self.scale.to_scale(magnitude) - self.intensity_range[0]) / range
+    }
+
+    // Inverse of `magnitude_to_frac`, except that 0 and below (black) is always silence.
+    fn frac_to_magnitude(&self, frac: f32) -> f32 {
+        if frac <= 0f32 {
+            return 0f32;
+        }
+        let range = self.intensity_range[1] - self.intensity_range[0];
+        self.scale
+            .from_scale(frac * range + self.intensity_range[0])
+    }
+
+    fn byte_to_magnitude(&self, byte: u8) -> f32 {
+        self.frac_to_magnitude(byte.to_frac())
+    }
 }
 
 #[derive(Clone, Copy)]
 pub struct SpectrogramPhasePlotSettings {
     pub bin_range: [usize; 2],
     pub lower_seam: f32,
+    // Relative images only. When set, each phase change is measured against the advance a
+    // sinusoid at the bin centre makes over one hop (the phase unwrapped along time), so
+    // steady partials come out flat instead of striped.
+    pub unwrap_along_time: Option<SpectrogramSettings>,
+}
+
+impl SpectrogramPhasePlotSettings {
+    fn expected_advance(&self, y: usize) -> f32 {
+        self.unwrap_along_time
+            .map_or(0f32, |s| s.phase_advance_per_bin::<f32>() * y as f32)
+    }
+
+    fn byte_to_phase(&self, byte: u8) -> f32 {
+        byte.to_frac() * TAU + self.lower_seam
+    }
 }
 
 trait PhaselessAmplitudeApplier {
-    fn apply_intensity(img: &mut SpectrogramImage, x: usize, y: usize, intensity: f32);
+    fn apply_intensity<F: SpectrogramFloat>(
+        img: &mut SpectrogramImage<F>,
+        x: usize,
+        y: usize,
+        intensity: F,
+    );
 }
 
 struct OverrideAmplitudeApplier;
 impl PhaselessAmplitudeApplier for OverrideAmplitudeApplier {
-    fn apply_intensity(img: &mut SpectrogramImage, x: usize, y: usize, intensity: f32) {
+    fn apply_intensity<F: SpectrogramFloat>(
+        img: &mut SpectrogramImage<F>,
+        x: usize,
+        y: usize,
+        intensity: F,
+    ) {
         *img.mut_get_at(x, y) = Complex::from(intensity);
     }
 }
 
 struct MultiplyByAmplitudeApplier;
 impl PhaselessAmplitudeApplier for MultiplyByAmplitudeApplier {
-    fn apply_intensity(img: &mut SpectrogramImage, x: usize, y: usize, intensity: f32) {
+    fn apply_intensity<F: SpectrogramFloat>(
+        img: &mut SpectrogramImage<F>,
+        x: usize,
+        y: usize,
+        intensity: F,
+    ) {
         *img.mut_get_at(x, y) *= intensity;
     }
 }
 
 trait ZeroingBehavior {
-    fn zero_outside_range(
-        img: &mut SpectrogramImage,
+    fn zero_outside_range<F: SpectrogramFloat>(
+        img: &mut SpectrogramImage<F>,
         x: usize,
         first_bin: usize,
         last_bin_plus_one: usize,
@@ -89,8 +283,8 @@ trait ZeroingBehavior {
 
 struct ZeroOutsideRange;
 impl ZeroingBehavior for ZeroOutsideRange {
-    fn zero_outside_range(
-        img: &mut SpectrogramImage,
+    fn zero_outside_range<F: SpectrogramFloat>(
+        img: &mut SpectrogramImage<F>,
         x: usize,
         first_bin: usize,
         last_bin_plus_one: usize,
@@ -106,8 +300,8 @@ impl ZeroingBehavior for ZeroOutsideRange {
 
 struct NoZeroing;
 impl ZeroingBehavior for NoZeroing {
-    fn zero_outside_range(
-        img: &mut SpectrogramImage,
+    fn zero_outside_range<F: SpectrogramFloat>(
+        img: &mut SpectrogramImage<F>,
         x: usize,
         first_bin: usize,
         last_bin_plus_one: usize,
@@ -115,21 +309,74 @@ impl ZeroingBehavior for NoZeroing {
     }
 }
 
+// Wraps a phase into [-pi, pi].
+pub(crate) fn wrap_phase<F: SpectrogramFloat>(phase: F) -> F {
+    phase - F::TAU() * (phase / F::TAU()).round()
+}
+
+// Builds a bin from polar form, keeping the DC and Nyquist bins (`y` of 0 or
+// `height - 1`) purely real as a real signal's spectrum requires.
+pub(crate) fn real_safe_polar<F: SpectrogramFloat>(
+    mag: F,
+    phase: F,
+    y: usize,
+    height: usize,
+) -> Complex<F> {
+    if y == 0 || y + 1 == height {
+        Complex::from(mag * phase.cos().signum())
+    } else {
+        Complex::from_polar(mag, phase)
+    }
+}
+
 impl SpectrogramImage {
-    pub fn compute_bin_number(window_size: usize, sample_rate: usize, frequency: f32) -> usize {
-        (frequency / sample_rate as f32 * window_size as f32) as usize
+    pub fn compute_bin_number(
+        settings: &SpectrogramSettings,
+        sample_rate: usize,
+        frequency: f32,
+    ) -> usize {
+        settings.frequency_bin(frequency, sample_rate as u32) as usize
     }
+}
 
-    pub fn get_at(&self, x: usize, y: usize) -> Complex32 {
-        self.data[y * self.width + x]
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    pub fn get_at(&self, x: usize, y: usize) -> Complex<F> {
+        self.data[self.get_index(x, y)]
     }
 
     pub fn get_index(&self, x: usize, y: usize) -> usize {
-        y * self.width + x
+        x * self.height + y
+    }
+
+    pub fn mut_get_at(&mut self, x: usize, y: usize) -> &mut Complex<F> {
+        let ind = self.get_index(x, y);
+        &mut self.data[ind]
+    }
+
+    pub fn column(&self, x: usize) -> &[Complex<F>] {
+        &self.data[x * self.height..(x + 1) * self.height]
+    }
+
+    pub fn column_mut(&mut self, x: usize) -> &mut [Complex<F>] {
+        &mut self.data[x * self.height..(x + 1) * self.height]
     }
 
-    pub fn mut_get_at(&mut self, x: usize, y: usize) -> &mut Complex32 {
-        &mut self.data[y * self.width + x]
+    pub fn columns(&self) -> std::slice::Chunks<'_, Complex<F>> {
+        self.data.chunks(self.height.max(1))
+    }
+
+    pub fn columns_mut(&mut self) -> std::slice::ChunksMut<'_, Complex<F>> {
+        self.data.chunks_mut(self.height.max(1))
+    }
+
+    // Strided view across time at a single bin.
+    pub fn row(&self, y: usize) -> std::iter::StepBy<std::slice::Iter<'_, Complex<F>>> {
+        self.data[y..].iter().step_by(self.height)
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> std::iter::StepBy<std::slice::IterMut<'_, Complex<F>>> {
+        let height = self.height;
+        self.data[y..].iter_mut().step_by(height)
     }
 
     pub fn phaseless_from_intensity_bytes(
@@ -154,19 +401,10 @@ impl SpectrogramImage {
         settings: &SpectrogramIntensityPlotSettings,
         buffer: &[u8],
     ) {
-        let range = settings.intensity_range[1] - settings.intensity_range[0];
         for x in 0..self.width {
             for y in settings.bin_range[0]..settings.bin_range[1] {
-                let buf_y = y - settings.bin_range[0];
-                let byte_val = buffer[(settings.bin_range[1] - 1 - buf_y) * self.width + x];
-                let as_float = if byte_val == 0 {
-                    f32::NEG_INFINITY
-                } else {
-                    byte_val.to_frac()
-                };
-                let un_normalized = as_float * range + settings.intensity_range[0];
-                let un_log = un_normalized.exp();
-                let intensity = un_log;
+                let byte_val = buffer[(settings.bin_range[1] - 1 - y) * self.width + x];
+                let intensity = F::lit(settings.byte_to_magnitude(byte_val) as f64);
                 Appl::apply_intensity(self, x, y, intensity);
             }
 
@@ -186,7 +424,7 @@ impl SpectrogramImage {
 
     pub fn normalize_magnitudes_no_nans(&mut self) {
         for c in &mut self.data {
-            *c = Complex::from_polar(1f32, c.arg());
+            *c = Complex::from_polar(F::one(), c.arg());
         }
     }
 
@@ -196,86 +434,118 @@ impl SpectrogramImage {
         }
     }
 
-    pub fn apply_phase_bytes(&mut self, lower_seam: f32, buffer: &[u8], relative: bool) {
-        for y in 0..self.height {
-            let mut phased = Complex::from(1f32);
+    // Replaces the phase of every bin in `bin_range` with the one encoded in `buffer`,
+    // keeping magnitudes. `relative` must match how the image was made.
+    pub fn apply_phase_bytes(
+        &mut self,
+        settings: &SpectrogramPhasePlotSettings,
+        buffer: &[u8],
+        relative: bool,
+    ) {
+        let height = self.height;
+        for y in settings.bin_range[0]..settings.bin_range[1] {
+            let row = (settings.bin_range[1] - 1 - y) * self.width;
+            let expected = settings.expected_advance(y);
+            let mut phase = 0f32;
             for x in 0..self.width {
-                let curr_byte = buffer[y * self.width];
-                let phase = curr_byte.to_frac() * TAU + lower_seam;
-                let curr_phase = (Complex::i() * phase).exp();
-                if relative {
-                    phased *= curr_phase;
+                let step = settings.byte_to_phase(buffer[row + x]);
+                phase = if relative && x > 0 {
+                    wrap_phase(phase + expected + step)
                 } else {
-                    phased = curr_phase;
-                }
-
-                *self.mut_get_at(x, y) *= phased;
+                    step
+                };
+                let mag = self.get_at(x, y).norm();
+                *self.mut_get_at(x, y) = real_safe_polar(mag, F::lit(phase as f64), y, height);
             }
         }
     }
 
+    // Unseeded uniform phases; see `apply_noise` for reproducible ones.
     pub fn apply_random_phases(&mut self) {
-        for x in 0..self.width {
-            for y in 0..self.height {
-                *self.mut_get_at(x, y) *= (Complex::i() * rand::random_range(0f32..TAU)).exp();
-            }
-        }
+        self.apply_noise(&noise::RandomPhases::default());
     }
 
-    pub fn apply_sinusoidal_phases(&mut self, assume_window_size: usize) {
-        let hop_size = assume_window_size / 2;
+    // Replaces every phase with one integrated along time from the spectral peaks
+    // (identity phase locking): each peak advances by its interpolated frequency per hop,
+    // and every bin in a peak's region takes that peak's phase. Magnitudes are kept.
+    pub fn apply_peak_locked_phases(&mut self, settings: &SpectrogramSettings) {
+        let advance_per_bin: F = settings.phase_advance_per_bin();
+        let nyquist = self.height.saturating_sub(1);
 
+        let mut prev_phases = vec![F::zero(); self.height];
+        let mut phases = vec![F::zero(); self.height];
         for x in 0..self.width {
-            for y in 0..self.height {
-                let samples_before_this = x * hop_size;
-                let actual_y = y; //self.height as i32 / 2 - y as i32 / 2 - 1;
-                // The 2 * pi and self.height * 2 cancel a 2. Though maybe it should be /assume_window_size * TAU with not quite cancellation. idk.
-                //let this_frequency = actual_y as f32 / self.height as f32 * PI;
-                //let this_frequency = actual_y as f32 / assume_window_size as f32 * TAU;
-                //let offset = 0f32;
-
-                let offset = if y < 27 || y == 28 { PI } else { 0f32 };
-
-                let this_frequency = 440f32 * TAU / 48000f32;
-
-                let this_frequency_hz = y as f32 / assume_window_size as f32 * 48000f32;
-
-                let radians_per_sec = this_frequency_hz * TAU;
-                let sec_per_hop = hop_size as f32 / 48000f32;
-
-                let radians_per_hop = sec_per_hop * radians_per_sec;
-
-                let radians_per_hop = -TAU / 4f32; // 600 HZ
+            let mags: Vec<F> = self.column(x).iter().map(|c| c.norm()).collect();
+            let regions = peaks::peak_regions(&mags);
+            if regions.is_empty() {
+                for (y, p) in phases.iter_mut().enumerate() {
+                    *p = prev_phases[y] + advance_per_bin * F::lit(y as f64);
+                }
+            }
+            for (range, bin) in regions {
+                let peak_y = bin.round().to_usize().unwrap().min(nyquist);
+                let phase = prev_phases[peak_y] + advance_per_bin * bin;
+                phases[range].fill(phase);
+            }
 
-                let radians_per_hop = 0f32; // 2400 HZ
+            let height = self.height;
+            for (y, c) in self.column_mut(x).iter_mut().enumerate() {
+                *c = real_safe_polar(c.norm(), phases[y], y, height);
+            }
+            std::mem::swap(&mut prev_phases, &mut phases);
+        }
+    }
 
-                // Guess:
-                let radians_per_hop = -TAU * this_frequency_hz / 2400f32;
-                //let radians_per_hop = 0.75f32 * TAU * this_frequency_hz / 2400f32;
+    // Gives every bin the phase of a stationary sinusoid, advancing by one hop's worth per
+    // column from zero at column 0. Magnitudes are kept. Only the hop to FFT length ratio
+    // matters, so no sample rate is needed.
+    pub fn apply_sinusoidal_phases(
+        &mut self,
+        settings: &SpectrogramSettings,
+        frequency: SinusoidFrequency,
+    ) {
+        let advance_per_bin: F = settings.phase_advance_per_bin();
+        let height = self.height;
 
-                // Guess #2:
-                let samples_per_cycle = 48000f32 / this_frequency_hz;
-                let leftover_samples = hop_size as f32 % samples_per_cycle;
-                let leftover_cycles = leftover_samples / samples_per_cycle;
-                //let radians_per_hop = leftover_cycles * TAU;
+        // Bins per bin of the unpadded window, which is what the Hann kernel is shaped in.
+        let window_bins = F::lit(settings.window_size as f64 / settings.fft_len() as f64);
 
-                //let this_frequency = y * PI * x;
-                *self.mut_get_at(x, y) *= (Complex::i() *
-                    //(this_frequency * samples_before_this as f32 + offset) as f32)
-                    //((y as f32) * x as f32 * PI + if y % 2 == 0 { 0f32 } else {PI}))
-                    if (y) % 2 == 1 {-0.498958833217f32 * x as f32 + PI} else {1.07799747917f32 * x as f32})
-                //radians_per_hop * x as f32)
-                //PI / 2f32)
-                .exp();
+        let mut phases = vec![F::zero(); height];
+        let mut bins: Vec<F> = (0..height).map(|y| F::lit(y as f64)).collect();
+        for x in 0..self.width {
+            if frequency == SinusoidFrequency::Instantaneous {
+                let mags: Vec<F> = self.column(x).iter().map(|c| c.norm()).collect();
+                for (range, bin) in peaks::peak_regions(&mags) {
+                    bins[range].fill(bin);
+                }
+            }
+            for (y, c) in self.column_mut(x).iter_mut().enumerate() {
+                if x > 0 {
+                    phases[y] = wrap_phase(phases[y] + advance_per_bin * bins[y]);
+                }
+                // A sinusoid's Hann sidelobes alternate in sign, starting negative two
+                // window bins out from its frequency.
+                let offset = ((F::lit(y as f64) - bins[y]) * window_bins).abs();
+                let negative = frequency == SinusoidFrequency::Instantaneous
+                    && offset > F::lit(2.0)
+                    && offset.floor().to_usize().unwrap().is_multiple_of(2);
+                let phase = if negative {
+                    phases[y] + F::PI()
+                } else {
+                    phases[y]
+                };
+                *c = real_safe_polar(c.norm(), phase, y, height);
             }
         }
     }
 
-    fn arg_seamed_at(complex: Complex32, lower_seam: f32) -> f32 {
-        let raw_arg = complex.arg();
-        let arg_0_tau = (raw_arg + TAU) % TAU;
-        let arg_0_tau_shifted = ((arg_0_tau - lower_seam) + TAU) % TAU;
-        arg_0_tau_shifted
+    fn arg_seamed_at(complex: Complex<F>, lower_seam: f32) -> f32 {
+        Self::seamed(complex.arg().as_f32(), lower_seam)
+    }
+
+    // Any phase, as an angle in [0, TAU) above `lower_seam`.
+    fn seamed(phase: f32, lower_seam: f32) -> f32 {
+        (phase - lower_seam).rem_euclid(TAU)
     }
 
     pub fn to_absolute_phase_bytes(
@@ -285,8 +555,7 @@ impl SpectrogramImage {
     ) {
         for x in 0..self.width {
             for y in settings.bin_range[0]..settings.bin_range[1] {
-                let buf_y = y - settings.bin_range[0];
-                buffer[(settings.bin_range[1] - 1 - buf_y) * self.width + x] =
+                buffer[(settings.bin_range[1] - 1 - y) * self.width + x] =
                     u8::as_frac(Self::arg_seamed_at(self.get_at(x, y), settings.lower_seam) / TAU);
             }
         }
@@ -297,20 +566,27 @@ impl SpectrogramImage {
         settings: &SpectrogramPhasePlotSettings,
         buffer: &mut [u8],
     ) {
-        for x in 0..self.width {
-            for y in settings.bin_range[0]..settings.bin_range[1] {
-                let buf_y = y - settings.bin_range[0];
-                if x > 0 {
-                    buffer[(settings.bin_range[1] - 1 - buf_y) * self.width + x] = u8::as_frac(
-                        Self::arg_seamed_at(
-                            self.get_at(x, y) / self.get_at(x - 1, y),
-                            settings.lower_seam,
-                        ) / TAU,
-                    );
+        // Each change is taken from the phase an importer will have reconstructed so far
+        // rather than the true previous phase, so quantization error doesn't pile up along
+        // the row.
+        for y in settings.bin_range[0]..settings.bin_range[1] {
+            let row = (settings.bin_range[1] - 1 - y) * self.width;
+            let expected = settings.expected_advance(y);
+            let mut decoded = 0f32;
+            for x in 0..self.width {
+                let phase = self.get_at(x, y).arg().as_f32();
+                let step = if x > 0 {
+                    phase - decoded - expected
                 } else {
-                    buffer[(settings.bin_range[1] - 1 - buf_y) * self.width + x] =
-                        u8::as_frac(Self::arg_seamed_at(self.get_at(x, y), settings.lower_seam));
-                }
+                    phase
+                };
+                let byte = u8::as_frac(Self::seamed(step, settings.lower_seam) / TAU);
+                buffer[row + x] = byte;
+                decoded = if x > 0 {
+                    wrap_phase(decoded + expected + settings.byte_to_phase(byte))
+                } else {
+                    settings.byte_to_phase(byte)
+                };
             }
         }
     }
@@ -328,18 +604,44 @@ impl SpectrogramImage {
         settings: &SpectrogramIntensityPlotSettings,
         buffer: &mut [u8],
     ) {
-        let range = settings.intensity_range[1] - settings.intensity_range[0];
         for x in 0..self.width {
             for y in settings.bin_range[0]..settings.bin_range[1] {
-                let buf_y = y - settings.bin_range[0];
-                buffer[(settings.bin_range[1] - 1 - buf_y) * self.width + x] = u8::as_frac(
-                    (self.get_at(x, y).norm_sqr().ln() * 0.5f32 - settings.intensity_range[0])
-                        / range,
-                );
+                buffer[(settings.bin_range[1] - 1 - y) * self.width + x] =
+                    u8::as_frac(settings.magnitude_to_frac(self.get_at(x, y).norm().as_f32()));
             }
         }
     }
 
+    // Plot settings whose range spans the given percentiles (0 to 100) of the scaled
+    // magnitudes in `bin_range`. Silent bins are left out so they don't drag log scales down.
+    pub fn auto_intensity_settings(
+        &self,
+        bin_range: [usize; 2],
+        scale: IntensityScale,
+        percentiles: [f32; 2],
+    ) -> SpectrogramIntensityPlotSettings {
+        let mut values: Vec<f32> = (0..self.width)
+            .flat_map(|x| self.column(x)[bin_range[0]..bin_range[1]].iter())
+            .map(|c| c.norm().as_f32())
+            .filter(|m| *m > 0f32)
+            .map(|m| scale.to_scale(m))
+            .filter(|v| v.is_finite())
+            .collect();
+        values.sort_by(|a, b| a.total_cmp(b));
+
+        let at = |p: f32| {
+            let i = (p.clamp(0f32, 100f32) / 100f32 * (values.len().max(1) - 1) as f32).round();
+            values.get(i as usize).copied().unwrap_or(0f32)
+        };
+        let low = at(percentiles[0]);
+        let high = at(percentiles[1]);
+        SpectrogramIntensityPlotSettings {
+            bin_range,
+            intensity_range: [low, if high > low { high } else { low + 1f32 }],
+            scale,
+        }
+    }
+
     pub fn create_intensity_bytes(&self, settings: &SpectrogramIntensityPlotSettings) -> Vec<u8> {
         let mut myvec = Vec::new();
         myvec.resize(
@@ -370,24 +672,19 @@ impl SpectrogramImage {
         myvec
     }
 
-    pub fn get_column(&self, x: usize, spectrum: &mut [Complex32]) {
-        for y in 0..self.height {
-            spectrum[y] = self.get_at(x, y);
-        }
-        for y in self.height..spectrum.len() {
-            spectrum[y] = Complex::ZERO;
-        }
+    pub fn get_column(&self, x: usize, spectrum: &mut [Complex<F>]) {
+        spectrum[..self.height].copy_from_slice(self.column(x));
+        spectrum[self.height..].fill(Complex::ZERO);
     }
 
-    pub fn set_column(&mut self, x: usize, spectrum: &[Complex32]) {
-        for y in 0..self.height {
-            *self.mut_get_at(x, y) = spectrum[y];
-        }
+    pub fn set_column(&mut self, x: usize, spectrum: &[Complex<F>]) {
+        let height = self.height;
+        self.column_mut(x).copy_from_slice(&spectrum[..height]);
     }
 
     pub fn new_empty(width: usize, height: usize) -> Self {
         let mut data = Vec::new();
-        data.resize(width * height, Complex32::ZERO);
+        data.resize(width * height, Complex::ZERO);
         Self {
             width,
             height,
@@ -396,6 +693,52 @@ impl SpectrogramImage {
     }
 }
 
+pub mod additive;
+
+pub mod color;
+
+pub mod envelope;
+
+pub mod eq;
+
+pub mod filter;
+
 pub mod forward;
 
+pub mod freeze;
+
 pub mod inverse;
+
+pub mod mask;
+
+pub mod metadata;
+
+pub mod morph;
+
+pub mod multires;
+
+pub mod noise;
+
+pub mod ops;
+
+pub mod padding;
+
+pub mod peaks;
+
+pub mod picture;
+
+pub mod resample;
+
+pub mod resize;
+
+pub mod retrieval;
+
+pub mod sinusoidal;
+
+pub mod stream;
+
+pub mod text;
+
+pub mod vocoder;
+
+pub mod wavelet;
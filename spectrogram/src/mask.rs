@@ -0,0 +1,67 @@
+use image::DynamicImage;
+
+use crate::{
+    IntensityScale, SpectrogramFloat, SpectrogramImage, SpectrogramIntensityPlotSettings,
+    filter::{FilterPhase, MagnitudeFilter},
+    picture::PictureSettings,
+};
+
+// Masks are spectrogram images whose magnitudes are gains: 1 passes a bin, 0 removes it.
+// That way they can be painted, filtered and resized like any other image.
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    // A mask the size of `self` with every gain `gain`.
+    pub fn uniform_mask(&self, gain: F) -> Self {
+        Self {
+            width: self.width,
+            height: self.height,
+            data: vec![gain.into(); self.data.len()],
+        }
+    }
+
+    // A mask from a greyscale picture laid over `width` columns and the frequency range of
+    // `settings` (whose duration and intensity settings are ignored). White passes, black
+    // and transparent block, and so does everything outside the frequency range.
+    pub fn mask_from_picture(
+        picture: &DynamicImage,
+        settings: &PictureSettings,
+        width: usize,
+    ) -> Self {
+        let gains = SpectrogramIntensityPlotSettings {
+            bin_range: [0, settings.settings.bin_count()],
+            intensity_range: [0f32, 1f32],
+            scale: IntensityScale::Linear,
+        };
+        Self::picture_over(picture, settings, width, &gains)
+    }
+
+    // Softens a mask's edges with a Gaussian blur, sigmas in columns and bins.
+    pub fn feather_mask(&mut self, sigma_time: f32, sigma_freq: f32) {
+        self.apply_filter(
+            &MagnitudeFilter::GaussianBlur {
+                sigma_time,
+                sigma_freq,
+            },
+            FilterPhase::Preserve,
+        );
+    }
+
+    // Scales every bin by the mask's gain there, clamped to [0, 1], or with `invert` by
+    // one minus it. Phases are kept.
+    pub fn apply_mask(&mut self, mask: &Self, invert: bool) {
+        self.assert_same_geometry(mask);
+        for (c, m) in self.data.iter_mut().zip(&mask.data) {
+            let gain = m.norm().min(F::one());
+            *c *= if invert { F::one() - gain } else { gain };
+        }
+    }
+
+    // What the mask passes and what it removes. The two add back up to `self`, and so do
+    // their resyntheses.
+    pub fn split_by_mask(&self, mask: &Self) -> (Self, Self) {
+        let mut kept = self.clone();
+        kept.apply_mask(mask, false);
+        let mut removed = self.clone();
+        removed.apply_mask(mask, true);
+        (kept, removed)
+    }
+}
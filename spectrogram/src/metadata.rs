@@ -0,0 +1,230 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use crate::{
+    IntensityScale, SpectrogramFloat, SpectrogramImage, SpectrogramIntensityPlotSettings,
+    SpectrogramSettings, additive::SynthesisEngine,
+};
+
+// Everything needed to turn an exported intensity image back into audio.
+#[derive(Clone, Copy)]
+pub struct ReconstructionMetadata {
+    pub settings: SpectrogramSettings,
+    pub sample_rate: u32,
+    pub intensity: SpectrogramIntensityPlotSettings,
+}
+
+#[derive(Debug)]
+pub enum MetadataError {
+    Io(std::io::Error),
+    Encoding(png::EncodingError),
+    Decoding(png::DecodingError),
+    // The PNG has no text chunk with this keyword, e.g. it went through an editor that
+    // strips them.
+    Missing(&'static str),
+    Invalid { keyword: &'static str, text: String },
+    // Image dimensions don't agree with the recorded bin range.
+    WrongSize,
+}
+
+impl From<std::io::Error> for MetadataError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<png::EncodingError> for MetadataError {
+    fn from(e: png::EncodingError) -> Self {
+        Self::Encoding(e)
+    }
+}
+
+impl From<png::DecodingError> for MetadataError {
+    fn from(e: png::DecodingError) -> Self {
+        Self::Decoding(e)
+    }
+}
+
+const WINDOW_SIZE: &str = "spectrogram.window_size";
+const WINDOW_PAD: &str = "spectrogram.window_pad";
+const SAMPLE_RATE: &str = "spectrogram.sample_rate";
+const BIN_RANGE: &str = "spectrogram.bin_range";
+const INTENSITY_RANGE: &str = "spectrogram.intensity_range";
+const INTENSITY_SCALE: &str = "spectrogram.intensity_scale";
+
+fn scale_to_text(scale: IntensityScale) -> String {
+    match scale {
+        IntensityScale::NaturalLog => "natural_log".to_string(),
+        IntensityScale::Decibels { reference } => format!("decibels {}", reference),
+        IntensityScale::Linear => "linear".to_string(),
+        IntensityScale::Power => "power".to_string(),
+        IntensityScale::Gamma { gamma } => format!("gamma {}", gamma),
+    }
+}
+
+fn scale_from_text(text: &str) -> Option<IntensityScale> {
+    let words: Vec<_> = text.split_whitespace().collect();
+    Some(match words.as_slice() {
+        ["natural_log"] => IntensityScale::NaturalLog,
+        ["decibels", reference] => IntensityScale::Decibels {
+            reference: reference.parse().ok()?,
+        },
+        ["linear"] => IntensityScale::Linear,
+        ["power"] => IntensityScale::Power,
+        ["gamma", gamma] => IntensityScale::Gamma {
+            gamma: gamma.parse().ok()?,
+        },
+        _ => return None,
+    })
+}
+
+fn pair_from_text<T: std::str::FromStr>(text: &str) -> Option<[T; 2]> {
+    let (a, b) = text.split_once(' ')?;
+    Some([a.trim().parse().ok()?, b.trim().parse().ok()?])
+}
+
+impl ReconstructionMetadata {
+    fn to_text_chunks(self) -> Vec<(&'static str, String)> {
+        let [bin_lo, bin_hi] = self.intensity.bin_range;
+        let [lo, hi] = self.intensity.intensity_range;
+        vec![
+            (WINDOW_SIZE, self.settings.window_size.to_string()),
+            (WINDOW_PAD, self.settings.window_pad_amnt.to_string()),
+            (SAMPLE_RATE, self.sample_rate.to_string()),
+            (BIN_RANGE, format!("{} {}", bin_lo, bin_hi)),
+            (INTENSITY_RANGE, format!("{} {}", lo, hi)),
+            (INTENSITY_SCALE, scale_to_text(self.intensity.scale)),
+        ]
+    }
+
+    fn from_text_chunks(chunks: &[png::text_metadata::TEXtChunk]) -> Result<Self, MetadataError> {
+        let find = |keyword: &'static str| {
+            chunks
+                .iter()
+                .find(|c| c.keyword == keyword)
+                .map(|c| c.text.as_str())
+                .ok_or(MetadataError::Missing(keyword))
+        };
+        fn parsed<T>(
+            keyword: &'static str,
+            text: &str,
+            value: Option<T>,
+        ) -> Result<T, MetadataError> {
+            value.ok_or_else(|| MetadataError::Invalid {
+                keyword,
+                text: text.to_string(),
+            })
+        }
+
+        let window_size = find(WINDOW_SIZE)?;
+        let window_pad = find(WINDOW_PAD)?;
+        let sample_rate = find(SAMPLE_RATE)?;
+        let bin_range = find(BIN_RANGE)?;
+        let intensity_range = find(INTENSITY_RANGE)?;
+        let scale = find(INTENSITY_SCALE)?;
+        // Edited or foreign files can hold anything; analysis needs an even window and
+        // nothing can be read from a reversed bin range.
+        let even_window = window_size
+            .parse()
+            .ok()
+            .filter(|&w: &usize| w > 0 && w % 2 == 0);
+        let ordered_bins = pair_from_text(bin_range).filter(|[lo, hi]: &[usize; 2]| lo <= hi);
+        Ok(Self {
+            settings: SpectrogramSettings {
+                window_size: parsed(WINDOW_SIZE, window_size, even_window)?,
+                window_pad_amnt: parsed(WINDOW_PAD, window_pad, window_pad.parse().ok())?,
+            },
+            sample_rate: parsed(SAMPLE_RATE, sample_rate, sample_rate.parse().ok())?,
+            intensity: SpectrogramIntensityPlotSettings {
+                bin_range: parsed(BIN_RANGE, bin_range, ordered_bins)?,
+                intensity_range: parsed(
+                    INTENSITY_RANGE,
+                    intensity_range,
+                    pair_from_text(intensity_range),
+                )?,
+                scale: parsed(INTENSITY_SCALE, scale, scale_from_text(scale))?,
+            },
+        })
+    }
+}
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    // Writes the grayscale intensity plot described by `metadata.intensity`, with the
+    // metadata itself in tEXt chunks.
+    pub fn save_intensity_png(
+        &self,
+        path: impl AsRef<Path>,
+        metadata: &ReconstructionMetadata,
+    ) -> Result<(), MetadataError> {
+        let bytes = self.create_intensity_bytes(&metadata.intensity);
+        let rows = metadata.intensity.bin_range[1] - metadata.intensity.bin_range[0];
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width as u32, rows as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        for (keyword, text) in metadata.to_text_chunks() {
+            encoder.add_text_chunk(keyword.to_string(), text)?;
+        }
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&bytes)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+impl SpectrogramImage {
+    // Reads a PNG written by `save_intensity_png` (possibly edited since) back into a
+    // phaseless spectrogram. Colour images are averaged down to gray.
+    pub fn load_intensity_png(
+        path: impl AsRef<Path>,
+    ) -> Result<(Self, ReconstructionMetadata), MetadataError> {
+        let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0u8; reader.output_buffer_size().unwrap()];
+        let frame = reader.next_frame(&mut buf)?;
+        let metadata =
+            ReconstructionMetadata::from_text_chunks(&reader.info().uncompressed_latin1_text)?;
+
+        let channels = frame.color_type.samples();
+        // Alpha, if any, is the last channel and is ignored.
+        let colour_channels = if channels % 2 == 0 {
+            channels - 1
+        } else {
+            channels
+        };
+        let gray: Vec<u8> = buf[..frame.buffer_size()]
+            .chunks(channels)
+            .map(|px| {
+                let sum: u32 = px[..colour_channels].iter().map(|&c| c as u32).sum();
+                (sum as f32 / colour_channels as f32).round() as u8
+            })
+            .collect();
+
+        let [bin_lo, bin_hi] = metadata.intensity.bin_range;
+        let height = metadata.settings.bin_count();
+        if frame.height as usize != bin_hi - bin_lo || bin_hi > height {
+            return Err(MetadataError::WrongSize);
+        }
+        let mut img = Self::new_empty(frame.width as usize, height);
+        img.phaseless_from_intensity_bytes(&metadata.intensity, &gray, true);
+        Ok((img, metadata))
+    }
+
+    // Loads an intensity PNG, gives it peak-locked phases and resynthesizes it with the
+    // recorded settings. Returns the samples and their sample rate.
+    pub fn reconstruct_from_png(
+        path: impl AsRef<Path>,
+        engine: SynthesisEngine,
+        thread_ct: usize,
+    ) -> Result<(Vec<f32>, u32), MetadataError> {
+        let (mut img, metadata) = Self::load_intensity_png(path)?;
+        img.apply_peak_locked_phases(&metadata.settings);
+        let samples = engine.synthesize(&img, &metadata.settings, thread_ct);
+        Ok((samples, metadata.sample_rate))
+    }
+}
@@ -0,0 +1,215 @@
+use rustfft::num_complex::Complex;
+
+use crate::{
+    SpectrogramFloat, SpectrogramImage, SpectrogramSettings,
+    envelope::{CepstralSmoother, log_floor, log_magnitudes},
+    peaks::{Peak, find_peaks},
+};
+
+// Morph amount over time, as `[position, amount]` keyframes. Positions are fractions of
+// the spectrogram width in [0, 1]; amount 0 is entirely the first sound, 1 the second.
+#[derive(Clone, Debug)]
+pub struct MorphCurve {
+    pub keyframes: Vec<[f32; 2]>,
+}
+
+impl MorphCurve {
+    pub fn constant(amount: f32) -> Self {
+        Self {
+            keyframes: vec![[0f32, amount]],
+        }
+    }
+
+    pub fn linear() -> Self {
+        Self {
+            keyframes: vec![[0f32, 0f32], [1f32, 1f32]],
+        }
+    }
+
+    pub fn at(&self, position: f32) -> f32 {
+        let Some(first) = self.keyframes.first() else {
+            return 0f32;
+        };
+        if position <= first[0] {
+            return first[1];
+        }
+        for pair in self.keyframes.windows(2) {
+            let [a, b] = [pair[0], pair[1]];
+            if position <= b[0] {
+                let t = (position - a[0]) / (b[0] - a[0]).max(f32::EPSILON);
+                return a[1] + (b[1] - a[1]) * t;
+            }
+        }
+        self.keyframes.last().unwrap()[1]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MorphSettings {
+    pub curve: MorphCurve,
+    // Cepstral coefficients kept for the spectral envelope; lower is smoother.
+    pub envelope_lifter: usize,
+    // Strongest peaks per column paired up as partials.
+    pub max_partials: usize,
+}
+
+impl Default for MorphSettings {
+    fn default() -> Self {
+        Self {
+            curve: MorphCurve::linear(),
+            envelope_lifter: 40,
+            max_partials: 48,
+        }
+    }
+}
+
+// Piecewise-linear map from the morphed bin axis back to a source bin axis, anchored at
+// the paired partials.
+struct BinWarp {
+    morphed: Vec<f32>,
+    source: Vec<f32>,
+}
+
+impl BinWarp {
+    fn source_bin(&self, morphed_bin: f32) -> f32 {
+        let seg = self
+            .morphed
+            .partition_point(|&m| m <= morphed_bin)
+            .clamp(1, self.morphed.len() - 1);
+        let (m0, m1) = (self.morphed[seg - 1], self.morphed[seg]);
+        let (s0, s1) = (self.source[seg - 1], self.source[seg]);
+        let t = (morphed_bin - m0) / (m1 - m0).max(f32::EPSILON);
+        s0 + (s1 - s0) * t
+    }
+}
+
+fn sample_linear<F: SpectrogramFloat>(values: &[F], pos: f32) -> F {
+    let pos = pos.clamp(0f32, (values.len() - 1) as f32);
+    let lo = pos.floor() as usize;
+    let hi = (lo + 1).min(values.len() - 1);
+    let t = F::lit((pos - lo as f32) as f64);
+    values[lo] + (values[hi] - values[lo]) * t
+}
+
+// Peaks more than 60 dB below the loudest bin of their frame are not treated as partials.
+fn partial_floor<F: SpectrogramFloat>(mags: &[F]) -> F {
+    let max_mag = mags.iter().fold(log_floor(), |a: F, &b| a.max(b));
+    max_mag * F::lit(1e-3)
+}
+
+// Pairs up partials whose frequencies, relative to the lowest partial of their own frame,
+// agree within `PAIRING_TOLERANCE` (in natural-log units). For harmonic sounds this pairs
+// harmonic numbers; unpaired partials just follow the warp of their neighbours.
+const PAIRING_TOLERANCE: f32 = 0.06;
+
+fn pair_partials<F: SpectrogramFloat>(a_peaks: &[Peak<F>], b_peaks: &[Peak<F>]) -> Vec<[f32; 2]> {
+    let (Some(a0), Some(b0)) = (a_peaks.first(), b_peaks.first()) else {
+        return vec![];
+    };
+    let relative = |p: &Peak<F>, base: &Peak<F>| (p.bin.as_f32() / base.bin.as_f32()).ln();
+
+    let mut pairs = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a_peaks.len() && j < b_peaks.len() {
+        let ra = relative(&a_peaks[i], a0);
+        let rb = relative(&b_peaks[j], b0);
+        if (ra - rb).abs() < PAIRING_TOLERANCE {
+            pairs.push([a_peaks[i].bin.as_f32(), b_peaks[j].bin.as_f32()]);
+            i += 1;
+            j += 1;
+        } else if ra < rb {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+// Builds the warps from the morphed axis (where each partial pair sits at its
+// interpolated frequency) to either source.
+fn partial_warps<F: SpectrogramFloat>(
+    a_peaks: &[Peak<F>],
+    b_peaks: &[Peak<F>],
+    amount: f32,
+    height: usize,
+) -> (BinWarp, BinWarp) {
+    let last = (height - 1) as f32;
+
+    let mut morphed = vec![0f32];
+    let mut from_a = vec![0f32];
+    let mut from_b = vec![0f32];
+    for [fa, fb] in pair_partials(a_peaks, b_peaks) {
+        let fm = fa * (1f32 - amount) + fb * amount;
+        // Pairing is monotonic in both sources, so only the morphed axis needs guarding.
+        if fm > *morphed.last().unwrap() && fm < last {
+            morphed.push(fm);
+            from_a.push(fa);
+            from_b.push(fb);
+        }
+    }
+    morphed.push(last);
+    from_a.push(last);
+    from_b.push(last);
+
+    (
+        BinWarp {
+            morphed: morphed.clone(),
+            source: from_a,
+        },
+        BinWarp {
+            morphed,
+            source: from_b,
+        },
+    )
+}
+
+// Morphs `a` into `b` along `settings.curve`. Each column is split into a cepstral
+// spectral envelope and the fine structure left over. Envelopes are interpolated at
+// fixed frequencies (so formants glide), while the fine structure of both sounds is
+// warped so paired partials meet at their interpolated frequencies before being mixed.
+// The result carries peak-locked phases and is ready for `inverse::inverse_mt`.
+pub fn morph<F: SpectrogramFloat>(
+    a: &SpectrogramImage<F>,
+    b: &SpectrogramImage<F>,
+    settings: &MorphSettings,
+    spectrogram_settings: &SpectrogramSettings,
+) -> SpectrogramImage<F> {
+    assert_eq!(a.height, b.height, "spectrogram heights differ");
+    let width = a.width.min(b.width);
+    let height = a.height;
+    let smoother = CepstralSmoother::new(height, settings.envelope_lifter);
+
+    let mut result = SpectrogramImage::new_empty(width, height);
+    for x in 0..width {
+        let amount = settings
+            .curve
+            .at(x as f32 / (width.max(2) - 1) as f32)
+            .clamp(0f32, 1f32);
+        let weight_b = F::lit(amount as f64);
+        let weight_a = F::one() - weight_b;
+
+        let log_a = log_magnitudes(a.column(x));
+        let log_b = log_magnitudes(b.column(x));
+        let env_a = smoother.smooth_log(&log_a);
+        let env_b = smoother.smooth_log(&log_b);
+        let fine_a: Vec<F> = log_a.iter().zip(&env_a).map(|(l, e)| *l - *e).collect();
+        let fine_b: Vec<F> = log_b.iter().zip(&env_b).map(|(l, e)| *l - *e).collect();
+
+        let mags_a: Vec<F> = a.column(x).iter().map(|c| c.norm()).collect();
+        let mags_b: Vec<F> = b.column(x).iter().map(|c| c.norm()).collect();
+        let peaks_a = find_peaks(&mags_a, partial_floor(&mags_a), settings.max_partials);
+        let peaks_b = find_peaks(&mags_b, partial_floor(&mags_b), settings.max_partials);
+        let (warp_a, warp_b) = partial_warps(&peaks_a, &peaks_b, amount, height);
+
+        for (y, out) in result.column_mut(x).iter_mut().enumerate() {
+            let envelope = env_a[y] * weight_a + env_b[y] * weight_b;
+            let fine = sample_linear(&fine_a, warp_a.source_bin(y as f32)) * weight_a
+                + sample_linear(&fine_b, warp_b.source_bin(y as f32)) * weight_b;
+            *out = Complex::from((envelope + fine).exp());
+        }
+    }
+
+    result.apply_peak_locked_phases(spectrogram_settings);
+    result
+}
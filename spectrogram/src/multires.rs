@@ -0,0 +1,203 @@
+use crate::{
+    SpectrogramFloat, SpectrogramImage, SpectrogramSettings, additive::SynthesisEngine,
+    forward::analyze_mt, ops::with_magnitude, resize::ResizeInterpolation,
+};
+
+// How the resolutions of a `MultiResolution` become one image.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ResolutionCombine {
+    // The quietest resolution at every point. Each resolution smears in its own direction,
+    // so the minimum keeps the sharpest of both: onsets from short windows, partials from
+    // long ones.
+    MinMagnitude,
+    // Each resolution covers a band, longest window lowest. `crossovers` holds the
+    // frequencies in Hz between neighbouring bands, ascending, one fewer than there are
+    // resolutions.
+    BandSplit { crossovers: Vec<f32> },
+}
+
+// Neighbouring bands crossfade over this ratio either side of their crossover.
+const CROSSOVER_WIDTH: f32 = 1.122_462; // 2^(1/6)
+
+// How much of `hz` belongs to band `band` of those divided at `crossovers`. The weights of
+// all bands add up to one as long as crossovers are a third of an octave apart.
+fn band_weight(crossovers: &[f32], band: usize, hz: f32) -> f32 {
+    let above = |c: f32| {
+        let t = (hz / (c / CROSSOVER_WIDTH)).ln() / (CROSSOVER_WIDTH * CROSSOVER_WIDTH).ln();
+        t.clamp(0f32, 1f32)
+    };
+    let lower = band.checked_sub(1).map_or(1f32, |i| above(crossovers[i]));
+    let upper = crossovers.get(band).map_or(1f32, |&c| 1f32 - above(c));
+    lower * upper
+}
+
+// The same sound analyzed at several window lengths, longest first.
+#[derive(Clone)]
+pub struct MultiResolution<F: SpectrogramFloat = f32> {
+    pub resolutions: Vec<(SpectrogramSettings, SpectrogramImage<F>)>,
+    pub sample_rate: u32,
+    // Length of the analyzed input.
+    pub len: usize,
+}
+
+impl<F: SpectrogramFloat> MultiResolution<F> {
+    pub fn analyze(
+        samples: &Vec<F>,
+        settings: &[SpectrogramSettings],
+        sample_rate: u32,
+        thread_ct: usize,
+    ) -> Self {
+        let mut settings = settings.to_vec();
+        settings.sort_by_key(|s| std::cmp::Reverse(s.window_size));
+        Self {
+            resolutions: settings
+                .into_iter()
+                .map(|s| (s, analyze_mt(samples, &s, thread_ct).unwrap()))
+                .collect(),
+            sample_rate,
+            len: samples.len(),
+        }
+    }
+
+    // Settings that describe a combined image's bins and levels: those of the longest
+    // window, whose bins are finest.
+    pub fn display_settings(&self) -> SpectrogramSettings {
+        self.resolutions
+            .iter()
+            .map(|(s, _)| *s)
+            .max_by_key(|s| s.fft_len())
+            .unwrap()
+    }
+
+    // The resolution whose columns a combined image shares: the one with the shortest hop.
+    fn finest_in_time(&self) -> &(SpectrogramSettings, SpectrogramImage<F>) {
+        self.resolutions
+            .iter()
+            .min_by_key(|(s, _)| s.hop_size())
+            .unwrap()
+    }
+
+    // Samples between a combined image's columns.
+    pub fn display_hop(&self) -> usize {
+        self.finest_in_time().0.hop_size()
+    }
+
+    // A crossover for each pair of neighbouring resolutions, where the longer window
+    // still holds 32 cycles.
+    pub fn default_crossovers(&self) -> Vec<f32> {
+        self.resolutions[..self.resolutions.len() - 1]
+            .iter()
+            .map(|(s, _)| 32f32 * self.sample_rate as f32 / s.window_size as f32)
+            .collect()
+    }
+
+    // Resolution `i` laid onto the combined grid: columns of the shortest hop, bins of the
+    // longest FFT, levels relative to `display_settings`' full scale.
+    fn on_display_grid(&self, i: usize, interpolation: ResizeInterpolation) -> SpectrogramImage<F> {
+        let display = self.display_settings();
+        let (time_settings, time_grid) = self.finest_in_time();
+        let (settings, img) = &self.resolutions[i];
+
+        let display_hop = time_settings.hop_size() as f64;
+        let display_offset = time_settings.analysis_offset(self.len) as f64;
+        let hop = settings.hop_size() as f64;
+        let offset = settings.analysis_offset(self.len) as f64;
+        let bin_ratio = settings.fft_len() as f64 / display.fft_len() as f64;
+        img.remap(
+            time_grid.width,
+            display.bin_count(),
+            |x| ((x as f64 + 1.0) * display_hop - display_offset + offset) / hop - 1.0,
+            |y| y as f64 * bin_ratio,
+            interpolation,
+            F::lit((display.full_scale_magnitude() / settings.full_scale_magnitude()) as f64),
+        )
+    }
+
+    // One image for display. Phases come from whichever resolution is nearest, so it is
+    // for looking at; resynthesize with `resynthesize`.
+    pub fn combine(
+        &self,
+        mode: &ResolutionCombine,
+        interpolation: ResizeInterpolation,
+    ) -> SpectrogramImage<F> {
+        let display = self.display_settings();
+        let grids: Vec<_> = (0..self.resolutions.len())
+            .map(|i| self.on_display_grid(i, interpolation))
+            .collect();
+        let mut out = grids[0].clone();
+        for (i, c) in out.data.iter_mut().enumerate() {
+            let y = i % out.height;
+            *c = match mode {
+                ResolutionCombine::MinMagnitude => grids
+                    .iter()
+                    .map(|g| g.data[i])
+                    .min_by(|a, b| a.norm().partial_cmp(&b.norm()).unwrap())
+                    .unwrap(),
+                ResolutionCombine::BandSplit { crossovers } => {
+                    let hz = display.bin_frequency(y, self.sample_rate);
+                    let weights = (0..grids.len()).map(|b| band_weight(crossovers, b, hz));
+                    let mag = grids
+                        .iter()
+                        .zip(weights.clone())
+                        .fold(F::zero(), |a, (g, w)| {
+                            a + g.data[i].norm() * F::lit(w as f64)
+                        });
+                    // Phase from the band that owns most of this bin.
+                    let (owner, _) = weights
+                        .enumerate()
+                        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                        .unwrap();
+                    let source = grids[owner].data[i];
+                    with_magnitude(source, source.norm(), mag)
+                }
+            };
+        }
+        out
+    }
+
+    // Band `band` of `crossovers`, resynthesized from its own resolution and aligned with
+    // the analyzed input. The bands add back up to the input.
+    pub fn resynthesize_band(
+        &self,
+        band: usize,
+        crossovers: &[f32],
+        engine: SynthesisEngine,
+        thread_ct: usize,
+    ) -> Vec<F> {
+        let (settings, img) = &self.resolutions[band];
+        let mut img = img.clone();
+        let weights: Vec<F> = (0..img.height)
+            .map(|y| {
+                let hz = settings.bin_frequency(y, self.sample_rate);
+                F::lit(band_weight(crossovers, band, hz) as f64)
+            })
+            .collect();
+        for (i, c) in img.data.iter_mut().enumerate() {
+            *c *= weights[i % img.height];
+        }
+        let samples = engine.synthesize(&img, settings, thread_ct);
+        let offset = settings.analysis_offset(self.len);
+        let mut out = samples[offset.min(samples.len())..].to_vec();
+        out.resize(self.len, F::zero());
+        out
+    }
+
+    // Every band from its own resolution, summed.
+    pub fn resynthesize(
+        &self,
+        crossovers: &[f32],
+        engine: SynthesisEngine,
+        thread_ct: usize,
+    ) -> Vec<F> {
+        let mut out = vec![F::zero(); self.len];
+        for band in 0..self.resolutions.len() {
+            for (o, s) in out
+                .iter_mut()
+                .zip(self.resynthesize_band(band, crossovers, engine, thread_ct))
+            {
+                *o += s;
+            }
+        }
+        out
+    }
+}
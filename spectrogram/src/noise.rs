@@ -0,0 +1,90 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::StandardNormal;
+
+use crate::{SpectrogramFloat, SpectrogramImage, real_safe_polar};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NoiseDistribution {
+    // Magnitudes kept exactly, phases uniform.
+    UniformPhase,
+    // Every bin scaled by complex Gaussian noise of unit power, so magnitudes scatter the
+    // way a real noise recording's do.
+    GaussianComplex,
+    // `GaussianComplex` tilted down 3 dB per octave, keeping the average power per column.
+    Pink,
+}
+
+// Where made-up phases come from. With a seed, the same image always gets the same noise.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RandomPhases {
+    pub distribution: NoiseDistribution,
+    pub seed: Option<u64>,
+}
+
+impl Default for RandomPhases {
+    fn default() -> Self {
+        Self {
+            distribution: NoiseDistribution::UniformPhase,
+            seed: None,
+        }
+    }
+}
+
+impl RandomPhases {
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..Default::default()
+        }
+    }
+
+    // A fresh generator: the same sequence every call when seeded. ChaCha8 rather than
+    // `StdRng`, whose algorithm may change between `rand` versions.
+    pub fn rng(&self) -> ChaCha8Rng {
+        match self.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_os_rng(),
+        }
+    }
+}
+
+pub(crate) fn random_phase<F: SpectrogramFloat>(rng: &mut impl Rng) -> F {
+    F::lit(rng.random_range(0f64..std::f64::consts::TAU))
+}
+
+// Amplitude weights for `NoiseDistribution::Pink`, scaled to a mean power of one. DC gets
+// the first bin's weight.
+fn pink_weights<F: SpectrogramFloat>(height: usize) -> Vec<F> {
+    let power: Vec<f64> = (0..height).map(|y| 1.0 / y.max(1) as f64).collect();
+    let mean = power.iter().sum::<f64>() / height as f64;
+    power.iter().map(|p| F::lit((p / mean).sqrt())).collect()
+}
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    // Replaces every phase according to `noise`, bins in storage order.
+    pub fn apply_noise(&mut self, noise: &RandomPhases) {
+        let height = self.height;
+        let mut rng = noise.rng();
+        let weights = match noise.distribution {
+            NoiseDistribution::Pink => pink_weights(height),
+            _ => vec![F::one(); height],
+        };
+        let half = F::lit(0.5).sqrt();
+        for (i, c) in self.data.iter_mut().enumerate() {
+            let y = i % height;
+            let mag = c.norm();
+            *c = match noise.distribution {
+                NoiseDistribution::UniformPhase => {
+                    real_safe_polar(mag, random_phase(&mut rng), y, height)
+                }
+                NoiseDistribution::GaussianComplex | NoiseDistribution::Pink => {
+                    let re: f64 = rng.sample(StandardNormal);
+                    let im: f64 = rng.sample(StandardNormal);
+                    let z = rustfft::num_complex::Complex::new(F::lit(re), F::lit(im)) * half;
+                    real_safe_polar(mag * z.norm() * weights[y], z.arg(), y, height)
+                }
+            };
+        }
+    }
+}
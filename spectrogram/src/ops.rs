@@ -0,0 +1,120 @@
+use rustfft::num_complex::Complex;
+
+use crate::{SpectrogramFloat, SpectrogramImage};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CombineOp {
+    Add,
+    Subtract,
+    // Masking: use a magnitude-only spectrogram (or gains) as `other`.
+    Multiply,
+    // 0 keeps `self`, 1 takes `other`.
+    Mix(f32),
+    Max,
+    Min,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CombineDomain {
+    // Operate on the complex bins directly, so phases interfere.
+    Complex,
+    // Operate on magnitudes only and keep the phase of `self`.
+    Magnitude,
+}
+
+// Rescales `bin` (whose magnitude is `old_mag`) to `new_mag`. Scaling rather than
+// rebuilding from polar keeps the DC and Nyquist bins exactly real.
+pub(crate) fn with_magnitude<F: SpectrogramFloat>(
+    bin: Complex<F>,
+    old_mag: F,
+    new_mag: F,
+) -> Complex<F> {
+    if old_mag > F::zero() {
+        bin * (new_mag / old_mag)
+    } else {
+        Complex::from(new_mag)
+    }
+}
+
+fn combine_bin<F: SpectrogramFloat>(
+    a: Complex<F>,
+    b: Complex<F>,
+    op: CombineOp,
+    domain: CombineDomain,
+) -> Complex<F> {
+    match domain {
+        CombineDomain::Complex => match op {
+            CombineOp::Add => a + b,
+            CombineOp::Subtract => a - b,
+            CombineOp::Multiply => a * b,
+            CombineOp::Mix(w) => {
+                let w = F::lit(w as f64);
+                a * (F::one() - w) + b * w
+            }
+            CombineOp::Max => {
+                if b.norm_sqr() > a.norm_sqr() {
+                    b
+                } else {
+                    a
+                }
+            }
+            CombineOp::Min => {
+                if b.norm_sqr() < a.norm_sqr() {
+                    b
+                } else {
+                    a
+                }
+            }
+        },
+        CombineDomain::Magnitude => {
+            let a_mag = a.norm();
+            let b_mag = b.norm();
+            let mag = match op {
+                CombineOp::Add => a_mag + b_mag,
+                // Spectral subtraction, floored at silence.
+                CombineOp::Subtract => (a_mag - b_mag).max(F::zero()),
+                CombineOp::Multiply => a_mag * b_mag,
+                CombineOp::Mix(w) => {
+                    let w = F::lit(w as f64);
+                    a_mag * (F::one() - w) + b_mag * w
+                }
+                CombineOp::Max => a_mag.max(b_mag),
+                CombineOp::Min => a_mag.min(b_mag),
+            };
+            with_magnitude(a, a_mag, mag)
+        }
+    }
+}
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    pub(crate) fn assert_same_geometry(&self, other: &Self) {
+        assert_eq!(self.width, other.width, "spectrogram widths differ");
+        assert_eq!(self.height, other.height, "spectrogram heights differ");
+    }
+
+    pub fn combine(&mut self, other: &Self, op: CombineOp, domain: CombineDomain) {
+        self.assert_same_geometry(other);
+        for (a, b) in self.data.iter_mut().zip(&other.data) {
+            *a = combine_bin(*a, *b, op, domain);
+        }
+    }
+
+    // Mixes from `self` (before `start_x`) to `other` (from `end_x` on) with a linear ramp in between.
+    pub fn crossfade(&mut self, other: &Self, start_x: usize, end_x: usize, domain: CombineDomain) {
+        self.assert_same_geometry(other);
+        assert!(start_x <= end_x);
+        let ramp_len = (end_x - start_x).max(1) as f32;
+        for x in 0..self.width {
+            let weight = if x < start_x {
+                0f32
+            } else if x >= end_x {
+                1f32
+            } else {
+                (x - start_x) as f32 / ramp_len
+            };
+            for (a, b) in self.column_mut(x).iter_mut().zip(other.column(x)) {
+                *a = combine_bin(*a, *b, CombineOp::Mix(weight), domain);
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{
+    SpectrogramFloat, SpectrogramImage, SpectrogramSettings, inverse::undo_to_real_no_changes,
+};
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    // The same columns at another amount of zero padding. Each column goes back to its
+    // windowed frame and is transformed again at `to`'s FFT length, so an analyzed
+    // spectrogram comes out as if it had been analyzed with `to` in the first place.
+    // Anything a painted column puts outside the window is lost on the way. None if the
+    // window sizes differ or the image isn't `from`'s height.
+    pub fn repad(&self, from: &SpectrogramSettings, to: &SpectrogramSettings) -> Option<Self> {
+        if from.window_size != to.window_size || self.height != from.bin_count() {
+            return None;
+        }
+
+        let mut planner = realfft::RealFftPlanner::new();
+        let ifft = planner.plan_fft_inverse(from.fft_len());
+        let fft = planner.plan_fft_forward(to.fft_len());
+        let scale = F::lit(from.fft_len() as f64).recip();
+
+        let mut out = Self::new_empty(self.width, to.bin_count());
+        let mut spectrum = ifft.make_input_vec();
+        for x in 0..self.width {
+            spectrum.copy_from_slice(self.column(x));
+            // A real frame's DC and Nyquist bins are real; drop whatever editing added.
+            let last = spectrum.len() - 1;
+            spectrum[0].im = F::zero();
+            spectrum[last].im = F::zero();
+
+            let mut frame =
+                undo_to_real_no_changes(&ifft, &mut spectrum, from.window_pad_amnt, false);
+            for s in &mut frame {
+                *s *= scale;
+            }
+            frame.resize(to.fft_len(), F::zero());
+            frame.rotate_left(to.window_size / 2);
+            fft.process(&mut frame, out.column_mut(x)).unwrap();
+        }
+        Some(out)
+    }
+}
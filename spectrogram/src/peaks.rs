@@ -0,0 +1,81 @@
+use std::ops::Range;
+
+use crate::SpectrogramFloat;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Peak<F: SpectrogramFloat = f32> {
+    // Fractional bin of the interpolated maximum.
+    pub bin: F,
+    pub magnitude: F,
+}
+
+// Fits a parabola through the log magnitudes around the local maximum at `k`.
+// Returns the offset from `k` (within [-0.5, 0.5]) and the interpolated magnitude.
+pub fn parabolic_interpolation<F: SpectrogramFloat>(mags: &[F], k: usize) -> (F, F) {
+    if k == 0 || k + 1 >= mags.len() {
+        return (F::zero(), mags[k]);
+    }
+    let tiny = F::min_positive_value();
+    let alpha = mags[k - 1].max(tiny).ln();
+    let beta = mags[k].max(tiny).ln();
+    let gamma = mags[k + 1].max(tiny).ln();
+    let denom = alpha - F::lit(2.0) * beta + gamma;
+    if denom.is_zero() {
+        return (F::zero(), mags[k]);
+    }
+    let half = F::lit(0.5);
+    let offset = (half * (alpha - gamma) / denom).max(-half).min(half);
+    let log_mag = beta - F::lit(0.25) * (alpha - gamma) * offset;
+    (offset, log_mag.exp())
+}
+
+// Local maxima of `mags` above `floor`, keeping at most `max_count` of the strongest.
+// The result is sorted by bin.
+pub fn find_peaks<F: SpectrogramFloat>(mags: &[F], floor: F, max_count: usize) -> Vec<Peak<F>> {
+    let mut peaks = vec![];
+    for k in 1..mags.len().saturating_sub(1) {
+        if mags[k] > floor && mags[k] > mags[k - 1] && mags[k] >= mags[k + 1] {
+            let (offset, magnitude) = parabolic_interpolation(mags, k);
+            peaks.push(Peak {
+                bin: F::lit(k as f64) + offset,
+                magnitude,
+            });
+        }
+    }
+
+    if peaks.len() > max_count {
+        peaks.sort_by(|a, b| b.magnitude.partial_cmp(&a.magnitude).unwrap());
+        peaks.truncate(max_count);
+        peaks.sort_by(|a, b| a.bin.partial_cmp(&b.bin).unwrap());
+    }
+    peaks
+}
+
+// Index of the quietest bin between two peak bins, used to split regions of influence.
+pub(crate) fn valley_between<F: SpectrogramFloat>(mags: &[F], lo: usize, hi: usize) -> usize {
+    (lo..=hi)
+        .min_by(|&a, &b| mags[a].partial_cmp(&mags[b]).unwrap())
+        .unwrap_or(lo)
+}
+
+// Splits a column into one region per peak above -80 dB of its loudest bin, divided at
+// the quietest bin between neighbouring peaks; the first region starts at bin 0 and the
+// last runs to the top. Each comes with its peak's interpolated bin.
+pub(crate) fn peak_regions<F: SpectrogramFloat>(mags: &[F]) -> Vec<(Range<usize>, F)> {
+    let max_mag = mags.iter().fold(F::zero(), |a, &b| a.max(b));
+    let found = find_peaks(mags, max_mag * F::lit(1e-4), usize::MAX);
+    let nyquist = mags.len().saturating_sub(1);
+    let bin_of = |peak: &Peak<F>| peak.bin.round().to_usize().unwrap().min(nyquist);
+
+    let mut regions = vec![];
+    let mut region_start = 0;
+    for (i, peak) in found.iter().enumerate() {
+        let region_end = match found.get(i + 1) {
+            Some(next) => valley_between(mags, bin_of(peak), bin_of(next)),
+            None => mags.len(),
+        };
+        regions.push((region_start..region_end, peak.bin));
+        region_start = region_end;
+    }
+    regions
+}
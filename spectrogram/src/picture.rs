@@ -0,0 +1,141 @@
+use image::DynamicImage;
+
+use crate::{
+    IntensityScale, SpectrogramFloat, SpectrogramImage, SpectrogramIntensityPlotSettings,
+    SpectrogramSettings, additive::SynthesisEngine, noise::RandomPhases, retrieval::PhaseRetrieval,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FrequencyAxis {
+    Linear,
+    // Equal image height per octave.
+    Log,
+}
+
+// How a picture is laid onto the time-frequency plane: its left edge at time 0, right
+// edge at `duration` seconds, bottom row at `frequency_range[0]` Hz and top row at
+// `frequency_range[1]`. Brightness maps onto `intensity_range` of `scale`, with black
+// always silent.
+#[derive(Clone, Copy)]
+pub struct PictureSettings {
+    pub settings: SpectrogramSettings,
+    pub sample_rate: u32,
+    pub duration: f32,
+    pub frequency_range: [f32; 2],
+    pub axis: FrequencyAxis,
+    pub intensity_range: [f32; 2],
+    pub scale: IntensityScale,
+    pub phase: PhaseRetrieval,
+    pub noise: RandomPhases,
+    pub engine: SynthesisEngine,
+}
+
+impl PictureSettings {
+    // Five seconds over 20 Hz to 16 kHz on a log axis, 80 dB of range below full scale.
+    pub fn new(settings: SpectrogramSettings, sample_rate: u32) -> Self {
+        Self {
+            settings,
+            sample_rate,
+            duration: 5f32,
+            frequency_range: [20f32, 16000f32],
+            axis: FrequencyAxis::Log,
+            intensity_range: [-80f32, 0f32],
+            scale: IntensityScale::dbfs(&settings),
+            phase: PhaseRetrieval::Pghi,
+            noise: RandomPhases::default(),
+            engine: SynthesisEngine::InverseFft { awful_hack: true },
+        }
+    }
+
+    // Fraction of the image height (0 at the bottom) where `hz` sits, if it's in range.
+    fn height_fraction(&self, hz: f32) -> Option<f32> {
+        let [lo, hi] = self.frequency_range;
+        let t = match self.axis {
+            FrequencyAxis::Linear => (hz - lo) / (hi - lo),
+            FrequencyAxis::Log => {
+                // A log axis can't reach 0 Hz.
+                let lo = lo.max(1f32);
+                (hz / lo).ln() / (hi / lo).ln()
+            }
+        };
+        (0f32..=1f32).contains(&t).then_some(t)
+    }
+}
+
+// Bilinear sample at fractional pixel coordinates, clamped to the edges.
+fn sample_bilinear(pixels: &[f32], width: usize, height: usize, u: f32, v: f32) -> f32 {
+    let u = u.clamp(0f32, (width - 1) as f32);
+    let v = v.clamp(0f32, (height - 1) as f32);
+    let (x0, y0) = (u.floor() as usize, v.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (tx, ty) = (u - x0 as f32, v - y0 as f32);
+    let at = |x: usize, y: usize| pixels[y * width + x];
+    let top = at(x0, y0) + (at(x1, y0) - at(x0, y0)) * tx;
+    let bottom = at(x0, y1) + (at(x1, y1) - at(x0, y1)) * tx;
+    top + (bottom - top) * ty
+}
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    // Resamples `picture` onto the bins and columns `settings` describe and turns its
+    // brightness into magnitudes. Transparent pixels are silent. Phases are all zero.
+    pub fn from_picture(picture: &DynamicImage, settings: &PictureSettings) -> Self {
+        let hop = settings.settings.hop_size();
+        let samples = (settings.duration * settings.sample_rate as f32) as usize;
+        let width = (samples / hop).max(1);
+        let intensity = SpectrogramIntensityPlotSettings {
+            bin_range: [0, settings.settings.bin_count()],
+            intensity_range: settings.intensity_range,
+            scale: settings.scale,
+        };
+        Self::picture_over(picture, settings, width, &intensity)
+    }
+
+    // `from_picture` stretched over exactly `width` columns instead of `settings.duration`,
+    // brightness mapped through `intensity`.
+    pub(crate) fn picture_over(
+        picture: &DynamicImage,
+        settings: &PictureSettings,
+        width: usize,
+        intensity: &SpectrogramIntensityPlotSettings,
+    ) -> Self {
+        let luma = picture.to_luma_alpha32f();
+        let (pic_width, pic_height) = (luma.width() as usize, luma.height() as usize);
+        let pixels: Vec<f32> = luma.pixels().map(|p| p.0[0] * p.0[1]).collect();
+        let height = settings.settings.bin_count();
+
+        // Picture row of every bin, or `None` outside the frequency range.
+        let rows: Vec<Option<f32>> = (0..height)
+            .map(|y| {
+                let hz = settings.settings.bin_frequency(y, settings.sample_rate);
+                let t = settings.height_fraction(hz)?;
+                Some((1f32 - t) * (pic_height - 1) as f32)
+            })
+            .collect();
+
+        let mut img = Self::new_empty(width, height);
+        for x in 0..width {
+            let u = (x as f32 + 0.5f32) / width as f32 * pic_width as f32 - 0.5f32;
+            for (y, row) in rows.iter().enumerate() {
+                if let Some(v) = row {
+                    let frac = sample_bilinear(&pixels, pic_width, pic_height, u, *v);
+                    *img.mut_get_at(x, y) = F::lit(intensity.frac_to_magnitude(frac) as f64).into();
+                }
+            }
+        }
+        img
+    }
+
+    // `from_picture`, then phases by `settings.phase` (drawing on `settings.noise`), then
+    // resynthesis by `settings.engine`.
+    pub fn picture_to_audio(
+        picture: &DynamicImage,
+        settings: &PictureSettings,
+        thread_ct: usize,
+    ) -> Vec<F> {
+        let mut img = Self::from_picture(picture, settings);
+        img.retrieve_phases(settings.phase, &settings.settings, &settings.noise);
+        settings
+            .engine
+            .synthesize(&img, &settings.settings, thread_ct)
+    }
+}
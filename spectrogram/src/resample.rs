@@ -0,0 +1,137 @@
+use crate::SpectrogramFloat;
+
+// Kaiser-windowed sinc resampling. The filter cuts off just below the lower of the two
+// Nyquist frequencies, so nothing aliases on the way down.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Resampler {
+    pub from: u32,
+    pub to: u32,
+    // Zero crossings of the sinc on either side of the centre tap. More is sharper.
+    pub zero_crossings: usize,
+    // Cutoff as a fraction of the lower Nyquist frequency.
+    pub rolloff: f64,
+    // Kaiser window shape; 8.6 gives about 90 dB of stopband rejection.
+    pub beta: f64,
+}
+
+// Largest polyphase table built up front; stranger ratios compute taps as they go.
+const MAX_TABLE_PHASES: usize = 4096;
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Zeroth order modified Bessel function of the first kind, by its power series.
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..50 {
+        term *= half / k as f64;
+        let t2 = term * term;
+        sum += t2;
+        if t2 < sum * 1e-17 {
+            break;
+        }
+    }
+    sum
+}
+
+impl Resampler {
+    pub fn new(from: u32, to: u32) -> Self {
+        assert!(from > 0 && to > 0, "sample rates must be positive");
+        Self {
+            from,
+            to,
+            zero_crossings: 32,
+            rolloff: 0.95,
+            beta: 8.6,
+        }
+    }
+
+    // Cutoff in cycles per input sample.
+    fn cutoff(&self) -> f64 {
+        0.5 * self.rolloff * (self.to as f64 / self.from as f64).min(1.0)
+    }
+
+    // Taps either side of the output position, in input samples.
+    fn half_width(&self) -> usize {
+        (self.zero_crossings as f64 / (2.0 * self.cutoff())).ceil() as usize
+    }
+
+    // Filter weight of an input sample `d` input samples before the output position.
+    fn kernel(&self, d: f64) -> f64 {
+        let fc = self.cutoff();
+        let reach = self.zero_crossings as f64 / (2.0 * fc);
+        if d.abs() >= reach {
+            return 0.0;
+        }
+        let x = 2.0 * fc * d;
+        let sinc = if x == 0.0 {
+            1.0
+        } else {
+            (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x)
+        };
+        let r = d / reach;
+        let window = bessel_i0(self.beta * (1.0 - r * r).sqrt()) / bessel_i0(self.beta);
+        2.0 * fc * sinc * window
+    }
+
+    // Weights for input samples `floor(t) - half_width + 1 ..= floor(t) + half_width`, where
+    // `t` is the output position in input samples and `frac` its fractional part.
+    fn taps(&self, frac: f64) -> Vec<f64> {
+        let half = self.half_width() as isize;
+        (-half + 1..=half)
+            .map(|k| self.kernel(frac - k as f64))
+            .collect()
+    }
+
+    // `samples` at `self.to`, covering the same span of time.
+    pub fn process<F: SpectrogramFloat>(&self, samples: &[F]) -> Vec<F> {
+        assert!(
+            self.from > 0 && self.to > 0,
+            "sample rates must be positive"
+        );
+        if self.from == self.to {
+            return samples.to_vec();
+        }
+        let g = gcd(self.from as u64, self.to as u64);
+        let (step, phases) = (self.from as u64 / g, self.to as u64 / g);
+        let len = (samples.len() as u64 * phases).div_ceil(step) as usize;
+        let table: Option<Vec<Vec<f64>>> = (phases as usize <= MAX_TABLE_PHASES).then(|| {
+            (0..phases)
+                .map(|p| self.taps(p as f64 / phases as f64))
+                .collect()
+        });
+
+        let half = self.half_width() as isize;
+        (0..len as u64)
+            .map(|n| {
+                // Output sample `n` sits at input position `n * step / phases`.
+                let base = (n * step / phases) as isize;
+                let phase = n * step % phases;
+                let computed;
+                let taps = match &table {
+                    Some(table) => &table[phase as usize],
+                    None => {
+                        computed = self.taps(phase as f64 / phases as f64);
+                        &computed
+                    }
+                };
+                let mut sum = 0f64;
+                for (i, w) in taps.iter().enumerate() {
+                    let k = base - half + 1 + i as isize;
+                    if let Some(s) = usize::try_from(k).ok().and_then(|k| samples.get(k)) {
+                        sum += w * s.to_f64().unwrap();
+                    }
+                }
+                F::lit(sum)
+            })
+            .collect()
+    }
+}
+
+// `samples` taken from `from` Hz to `to` Hz with the default filter.
+pub fn resample<F: SpectrogramFloat>(samples: &[F], from: u32, to: u32) -> Vec<F> {
+    Resampler::new(from, to).process(samples)
+}
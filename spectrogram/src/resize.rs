@@ -0,0 +1,176 @@
+use crate::{SpectrogramFloat, SpectrogramImage, SpectrogramSettings, real_safe_polar};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ResizeInterpolation {
+    Nearest,
+    Linear,
+    // Catmull-Rom, floored at silence where it overshoots.
+    Cubic,
+}
+
+// Source indices and weights for fractional position `pos` along an axis of `len`
+// samples, clamping at the ends.
+fn taps(pos: f64, len: usize, interpolation: ResizeInterpolation) -> Vec<(usize, f64)> {
+    let last = len as f64 - 1.0;
+    let pos = pos.clamp(0.0, last);
+    let clamp = |i: f64| i.clamp(0.0, last) as usize;
+    let base = pos.floor();
+    let t = pos - base;
+    match interpolation {
+        ResizeInterpolation::Nearest => vec![(clamp(pos.round()), 1.0)],
+        ResizeInterpolation::Linear => vec![(clamp(base), 1.0 - t), (clamp(base + 1.0), t)],
+        ResizeInterpolation::Cubic => {
+            let t2 = t * t;
+            let t3 = t2 * t;
+            vec![
+                (clamp(base - 1.0), (-t3 + 2.0 * t2 - t) / 2.0),
+                (clamp(base), (3.0 * t3 - 5.0 * t2 + 2.0) / 2.0),
+                (clamp(base + 1.0), (-3.0 * t3 + 4.0 * t2 + t) / 2.0),
+                (clamp(base + 2.0), (t3 - t2) / 2.0),
+            ]
+        }
+    }
+}
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    // A `width` by `height` image whose bin `(x, y)` is read from fractional position
+    // `(column(x), bin(y))` of this one. Magnitudes are interpolated and scaled by `gain`;
+    // phases come from the nearest bin, so analyzed material wants its phases retrieved
+    // again afterwards. Bins reading from above the top one stay silent.
+    pub(crate) fn remap(
+        &self,
+        width: usize,
+        height: usize,
+        column: impl Fn(usize) -> f64,
+        bin: impl Fn(usize) -> f64,
+        interpolation: ResizeInterpolation,
+        gain: F,
+    ) -> Self {
+        let mut out = Self::new_empty(width, height);
+        if self.width == 0 || self.height == 0 {
+            return out;
+        }
+        let columns: Vec<_> = (0..width)
+            .map(|x| taps(column(x), self.width, interpolation))
+            .collect();
+        let top = self.height as f64 - 0.5;
+        let bins: Vec<_> = (0..height)
+            .map(&bin)
+            .take_while(|&pos| pos < top)
+            .map(|pos| taps(pos, self.height, interpolation))
+            .collect();
+        let nearest = |taps: &[(usize, f64)]| {
+            taps.iter()
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .unwrap()
+                .0
+        };
+
+        for (x, column_taps) in columns.iter().enumerate() {
+            let source_x = nearest(column_taps);
+            for (y, bin_taps) in bins.iter().enumerate() {
+                let mut mag = 0f64;
+                for &(sx, wx) in column_taps {
+                    for &(sy, wy) in bin_taps {
+                        mag += wx * wy * self.get_at(sx, sy).norm().to_f64().unwrap();
+                    }
+                }
+                let phase = self.get_at(source_x, nearest(bin_taps)).arg();
+                *out.mut_get_at(x, y) =
+                    real_safe_polar(F::lit(mag.max(0.0)) * gain, phase, y, height);
+            }
+        }
+        out
+    }
+
+    // Stretches or squeezes the time axis to `width` columns, first and last columns
+    // staying put.
+    pub fn resize_time(&self, width: usize, interpolation: ResizeInterpolation) -> Self {
+        let ratio = (self.width as f64 - 1.0) / (width as f64 - 1.0).max(1.0);
+        self.remap(
+            width,
+            self.height,
+            |x| x as f64 * ratio,
+            |y| y as f64,
+            interpolation,
+            F::one(),
+        )
+    }
+
+    // Stretches or squeezes the frequency axis to `height` bins, DC and Nyquist staying put.
+    pub fn resize_frequency(&self, height: usize, interpolation: ResizeInterpolation) -> Self {
+        let ratio = (self.height as f64 - 1.0) / (height as f64 - 1.0).max(1.0);
+        self.remap(
+            self.width,
+            height,
+            |x| x as f64,
+            |y| y as f64 * ratio,
+            interpolation,
+            F::one(),
+        )
+    }
+
+    pub fn resize(&self, width: usize, height: usize, interpolation: ResizeInterpolation) -> Self {
+        let x_ratio = (self.width as f64 - 1.0) / (width as f64 - 1.0).max(1.0);
+        let y_ratio = (self.height as f64 - 1.0) / (height as f64 - 1.0).max(1.0);
+        self.remap(
+            width,
+            height,
+            |x| x as f64 * x_ratio,
+            |y| y as f64 * y_ratio,
+            interpolation,
+            F::one(),
+        )
+    }
+
+    // The same sound laid out for `to` instead of `from`: bins keep their frequencies,
+    // columns keep their times (centres as in `inverse::inverse_mt`), and magnitudes are
+    // rescaled so a full-scale sinusoid stays full scale. The result is as long as the
+    // original would resynthesize to.
+    pub fn convert_settings(
+        &self,
+        from: &SpectrogramSettings,
+        to: &SpectrogramSettings,
+        interpolation: ResizeInterpolation,
+    ) -> Self {
+        let from_hop = from.hop_size() as f64;
+        let to_hop = to.hop_size() as f64;
+        let duration = from_hop * (self.width as f64 + 1.0);
+        let width = ((duration / to_hop).round() as usize)
+            .saturating_sub(1)
+            .max(1);
+        let bin_ratio = from.fft_len() as f64 / to.fft_len() as f64;
+        let gain = F::lit((to.full_scale_magnitude() / from.full_scale_magnitude()) as f64);
+        self.remap(
+            width,
+            to.bin_count(),
+            |x| (x as f64 + 1.0) * to_hop / from_hop - 1.0,
+            |y| y as f64 * bin_ratio,
+            interpolation,
+            gain,
+        )
+    }
+
+    // The same sound at `to_rate` Hz instead of `from_rate`, analyzed with the same window
+    // length in samples: bins keep their frequencies and columns their times. Anything
+    // above the new Nyquist frequency is dropped.
+    pub fn convert_sample_rate(
+        &self,
+        from_rate: u32,
+        to_rate: u32,
+        interpolation: ResizeInterpolation,
+    ) -> Self {
+        let ratio = from_rate as f64 / to_rate as f64;
+        let width = (((self.width as f64 + 1.0) / ratio).round() as usize)
+            .saturating_sub(1)
+            .max(1);
+        self.remap(
+            width,
+            self.height,
+            |x| (x as f64 + 1.0) * ratio - 1.0,
+            |y| y as f64 / ratio,
+            interpolation,
+            F::one(),
+        )
+    }
+}
@@ -0,0 +1,215 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::{
+    SpectrogramFloat, SpectrogramImage, SpectrogramSettings,
+    forward::analyze_shifted_real_with_hann_window,
+    noise::{RandomPhases, random_phase},
+    real_safe_polar,
+    stream::StreamingSynthesizer,
+    wrap_phase,
+};
+
+// Ways of making up phases for a magnitude-only spectrogram.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PhaseRetrieval {
+    Random,
+    PeakLocked,
+    // Random phases refined by this many Griffin-Lim iterations.
+    GriffinLim { iterations: usize },
+    // Phase gradient heuristic integration: one pass, no iterations.
+    Pghi,
+}
+
+// Time-frequency spread of the Gaussian that best matches a Hann window of `window_size`
+// samples, in samples squared (Průša et al., "A Noniterative Method for Reconstruction of
+// Phase From STFT Magnitude").
+fn hann_gaussian_lambda(window_size: usize) -> f64 {
+    0.25645 * (window_size * window_size) as f64
+}
+
+// Bins quieter than this, relative to the loudest, get random phases in PGHI.
+const PGHI_TOLERANCE: f64 = 1e-5;
+
+struct Pending<F> {
+    magnitude: F,
+    x: usize,
+    y: usize,
+}
+
+impl<F: SpectrogramFloat> PartialEq for Pending<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<F: SpectrogramFloat> Eq for Pending<F> {}
+
+impl<F: SpectrogramFloat> PartialOrd for Pending<F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: SpectrogramFloat> Ord for Pending<F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.magnitude
+            .partial_cmp(&other.magnitude)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    // `noise` supplies every random phase a method needs, so seeding it makes the result
+    // reproducible.
+    pub fn retrieve_phases(
+        &mut self,
+        method: PhaseRetrieval,
+        settings: &SpectrogramSettings,
+        noise: &RandomPhases,
+    ) {
+        match method {
+            PhaseRetrieval::Random => self.apply_noise(noise),
+            PhaseRetrieval::PeakLocked => self.apply_peak_locked_phases(settings),
+            PhaseRetrieval::GriffinLim { iterations } => {
+                self.apply_noise(noise);
+                self.griffin_lim(settings, iterations);
+            }
+            PhaseRetrieval::Pghi => self.apply_pghi_phases(settings, noise),
+        }
+    }
+
+    // Refines the current phases by alternately resynthesizing and reanalyzing, each time
+    // keeping the new phases but restoring the original magnitudes.
+    pub fn griffin_lim(&mut self, settings: &SpectrogramSettings, iterations: usize) {
+        let target: Vec<F> = self.data.iter().map(|c| c.norm()).collect();
+        let hop_size = settings.hop_size();
+        let mut planner = realfft::RealFftPlanner::new();
+        let fft = planner.plan_fft_forward(settings.fft_len());
+        let height = self.height;
+
+        for _ in 0..iterations {
+            let mut synth = StreamingSynthesizer::new(settings, true);
+            let mut samples = synth.push_columns(self);
+            samples.extend(synth.finish());
+
+            for x in 0..self.width {
+                let seg = &samples[x * hop_size..x * hop_size + settings.window_size];
+                let analyzed =
+                    analyze_shifted_real_with_hann_window(&fft, seg, settings.window_pad_amnt);
+                for (y, (out, bin)) in self.column_mut(x).iter_mut().zip(analyzed).enumerate() {
+                    *out = real_safe_polar(target[x * height + y], bin.arg(), y, height);
+                }
+            }
+        }
+    }
+
+    // Integrates phase from the gradients the log magnitude implies, assuming the Hann
+    // window behaves like a Gaussian. Starts from the loudest bin and spreads outward
+    // through its loudest neighbours, so phase relationships are best where they matter.
+    // Bins too quiet to judge get uniform phases from `noise`.
+    pub fn apply_pghi_phases(&mut self, settings: &SpectrogramSettings, noise: &RandomPhases) {
+        let (width, height) = (self.width, self.height);
+        if width == 0 || height == 0 {
+            return;
+        }
+        let hop = F::lit(settings.hop_size() as f64);
+        let fft_len = F::lit(settings.fft_len() as f64);
+        let lambda = F::lit(hann_gaussian_lambda(settings.window_size));
+        let per_bin: F = settings.phase_advance_per_bin();
+
+        let mags: Vec<F> = self.data.iter().map(|c| c.norm()).collect();
+        let max_mag = mags.iter().fold(F::zero(), |a, &b| a.max(b));
+        let tolerance = max_mag * F::lit(PGHI_TOLERANCE);
+        let tiny = F::min_positive_value();
+        let logs: Vec<F> = mags.iter().map(|m| m.max(tiny).ln()).collect();
+        let at = |x: usize, y: usize| x * height + y;
+
+        // Centred differences, one-sided at the edges.
+        let diff = |lo: usize, hi: usize, step: F| (logs[hi] - logs[lo]) / step;
+        let time_advance: Vec<F> = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i / height, i % height);
+                let (lo, hi) = (y.saturating_sub(1), (y + 1).min(height - 1));
+                let d = if hi > lo {
+                    diff(at(x, lo), at(x, hi), F::lit((hi - lo) as f64))
+                } else {
+                    F::zero()
+                };
+                per_bin * F::lit(y as f64) + hop * fft_len / lambda * d
+            })
+            .collect();
+        let freq_advance: Vec<F> = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i / height, i % height);
+                let (lo, hi) = (x.saturating_sub(1), (x + 1).min(width - 1));
+                let d = if hi > lo {
+                    diff(at(lo, y), at(hi, y), F::lit((hi - lo) as f64))
+                } else {
+                    F::zero()
+                };
+                -lambda / (hop * fft_len) * d
+            })
+            .collect();
+
+        let mut rng = noise.rng();
+        let mut phases: Vec<F> = (0..width * height)
+            .map(|_| random_phase(&mut rng))
+            .collect();
+        let mut done: Vec<bool> = mags.iter().map(|&m| m <= tolerance).collect();
+
+        let mut by_magnitude: Vec<usize> = (0..width * height).filter(|&i| !done[i]).collect();
+        by_magnitude.sort_by(|&a, &b| mags[b].partial_cmp(&mags[a]).unwrap_or(Ordering::Equal));
+
+        let half = F::lit(0.5);
+        let mut heap = BinaryHeap::new();
+        for seed in by_magnitude {
+            if done[seed] {
+                continue;
+            }
+            done[seed] = true;
+            phases[seed] = F::zero();
+            heap.push(Pending {
+                magnitude: mags[seed],
+                x: seed / height,
+                y: seed % height,
+            });
+
+            while let Some(Pending { x, y, .. }) = heap.pop() {
+                let here = at(x, y);
+                let mut neighbours = vec![];
+                if x + 1 < width {
+                    let n = at(x + 1, y);
+                    neighbours.push((n, (time_advance[here] + time_advance[n]) * half));
+                }
+                if x > 0 {
+                    let n = at(x - 1, y);
+                    neighbours.push((n, -(time_advance[here] + time_advance[n]) * half));
+                }
+                if y + 1 < height {
+                    let n = at(x, y + 1);
+                    neighbours.push((n, (freq_advance[here] + freq_advance[n]) * half));
+                }
+                if y > 0 {
+                    let n = at(x, y - 1);
+                    neighbours.push((n, -(freq_advance[here] + freq_advance[n]) * half));
+                }
+                for (n, step) in neighbours {
+                    if done[n] {
+                        continue;
+                    }
+                    done[n] = true;
+                    phases[n] = wrap_phase(phases[here] + step);
+                    heap.push(Pending {
+                        magnitude: mags[n],
+                        x: n / height,
+                        y: n % height,
+                    });
+                }
+            }
+        }
+
+        for (i, c) in self.data.iter_mut().enumerate() {
+            *c = real_safe_polar(mags[i], phases[i], i % height, height);
+        }
+    }
+}
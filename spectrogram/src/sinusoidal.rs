@@ -0,0 +1,250 @@
+use crate::{
+    SpectrogramFloat, SpectrogramImage, SpectrogramSettings,
+    additive::{Partial, render_partials},
+    forward::analyze_shifted_real_with_hann_window,
+    inverse::inverse_mt,
+    noise::RandomPhases,
+    peaks::find_peaks,
+    wrap_phase,
+};
+
+// How spectral peaks are picked and linked into partials.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PeakTracking {
+    // Most peaks kept per column, loudest first.
+    pub max_count: usize,
+    // Peaks quieter than this many dB below the loudest bin are ignored.
+    pub floor_db: f32,
+    // Peaks further apart than this (in bins) between columns belong to different partials.
+    pub max_jump: f32,
+    // Partials lasting fewer columns than this are dropped.
+    pub min_columns: usize,
+    // Refine each peak's frequency from its phase advance since the previous column, rather
+    // than trusting the parabolic fit alone. Needs real phases, so not for painted images.
+    pub instantaneous_frequency: bool,
+}
+
+impl Default for PeakTracking {
+    fn default() -> Self {
+        Self {
+            max_count: 64,
+            floor_db: -80f32,
+            max_jump: 1f32,
+            min_columns: 3,
+            instantaneous_frequency: true,
+        }
+    }
+}
+
+impl<F: SpectrogramFloat> Partial<F> {
+    // Multiplies every frequency by `2^(semitones / 12)`.
+    pub fn transpose(&mut self, semitones: F) {
+        let ratio = F::lit(2.0).powf(semitones / F::lit(12.0));
+        for bin in &mut self.bins {
+            *bin *= ratio;
+        }
+    }
+
+    // Sets the starting phase so the rendered partial has `phase` at the centre of column
+    // `x`, which must be one it sounds in, counting phase the way `additive::render_partials`
+    // accumulates it.
+    pub fn anchor_phase(&mut self, x: usize, phase: F, settings: &SpectrogramSettings) {
+        let offset = x.checked_sub(self.first_column);
+        assert!(offset.is_some_and(|i| i < self.bins.len()));
+        let hop = F::lit(settings.hop_size() as f64);
+        let radians_per_bin = F::TAU() / F::lit(settings.fft_len() as f64);
+        // Sum over one hop of a frequency ramping linearly from `a` towards `b`.
+        let per_hop =
+            |a: F, b: F| (hop * a + (b - a) * (hop - F::one()) * F::lit(0.5)) * radians_per_bin;
+        let advance = self.bins[..=offset.unwrap()]
+            .windows(2)
+            .fold(F::zero(), |sum, w| sum + per_hop(w[0], w[1]));
+        self.phase = wrap_phase(phase - advance);
+    }
+
+    // Frequency in bins at column `x`, if the partial is sounding there.
+    pub fn bin_at(&self, x: usize) -> Option<F> {
+        self.bins.get(x.checked_sub(self.first_column)?).copied()
+    }
+}
+
+impl<F: SpectrogramFloat> SpectrogramImage<F> {
+    // Frequency in bins of the sinusoid behind bin `y` of column `x`, from how far its phase
+    // moved over the neighbouring hop beyond what bin `y`'s centre frequency accounts for.
+    fn instantaneous_bin(&self, x: usize, y: usize, settings: &SpectrogramSettings) -> Option<F> {
+        let (a, b) = if x > 0 {
+            (x - 1, x)
+        } else if self.width > 1 {
+            (0, 1)
+        } else {
+            return None;
+        };
+        let per_bin: F = settings.phase_advance_per_bin();
+        let expected = per_bin * F::lit(y as f64);
+        let deviation = wrap_phase(self.get_at(b, y).arg() - self.get_at(a, y).arg() - expected);
+        Some(F::lit(y as f64) + deviation / per_bin)
+    }
+
+    // Picks the peaks of every column and links each one to the nearest unclaimed peak of
+    // the previous column within `tracking.max_jump`, loudest first. The rest start new
+    // partials.
+    pub fn track_partials(
+        &self,
+        settings: &SpectrogramSettings,
+        tracking: &PeakTracking,
+    ) -> Vec<Partial<F>> {
+        let max_mag = self.data.iter().fold(F::zero(), |a, b| a.max(b.norm()));
+        let floor = max_mag * F::lit(10f64.powf(tracking.floor_db as f64 / 20.0));
+        let max_jump = F::lit(tracking.max_jump as f64);
+
+        let mut finished = vec![];
+        let mut active: Vec<Partial<F>> = vec![];
+        let mut mags = vec![F::zero(); self.height];
+        for x in 0..self.width {
+            for (m, c) in mags.iter_mut().zip(self.column(x)) {
+                *m = c.norm();
+            }
+            let mut peaks = find_peaks(&mags, floor, tracking.max_count);
+            peaks.sort_by(|a, b| b.magnitude.partial_cmp(&a.magnitude).unwrap());
+
+            let mut continued = vec![];
+            for peak in peaks {
+                let y = peak.bin.round().to_usize().unwrap();
+                let bin = if tracking.instantaneous_frequency {
+                    self.instantaneous_bin(x, y, settings).unwrap_or(peak.bin)
+                } else {
+                    peak.bin
+                };
+                let amplitude = self.oscillator_amplitude(peak.magnitude, y, settings);
+                let nearest = active
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| (i, (*p.bins.last().unwrap() - bin).abs()))
+                    .filter(|&(_, d)| d <= max_jump)
+                    .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+                match nearest {
+                    Some((i, _)) => {
+                        let mut partial = active.swap_remove(i);
+                        partial.bins.push(bin);
+                        partial.amplitudes.push(amplitude);
+                        continued.push(partial);
+                    }
+                    None => {
+                        continued.push(Partial::new(x, bin, amplitude, self.get_at(x, y).arg()))
+                    }
+                }
+            }
+            finished.append(&mut active);
+            active = continued;
+        }
+        finished.append(&mut active);
+        finished.retain(|p| p.bins.len() >= tracking.min_columns);
+
+        // Onsets are where frequency estimates are worst, so take each partial's phase from
+        // its loudest column instead of its first.
+        if tracking.instantaneous_frequency {
+            for partial in &mut finished {
+                let (i, _) = partial
+                    .amplitudes
+                    .iter()
+                    .enumerate()
+                    .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+                    .unwrap();
+                let x = partial.first_column + i;
+                let y = partial.bins[i]
+                    .round()
+                    .to_usize()
+                    .unwrap()
+                    .min(self.height - 1);
+                partial.anchor_phase(x, self.get_at(x, y).arg(), settings);
+            }
+        }
+        finished
+    }
+}
+
+// Partials plus whatever they don't account for, as in Serra's spectral modeling synthesis:
+// the residual keeps only magnitudes and is resynthesized as noise.
+#[derive(Clone)]
+pub struct SinusoidalModel<F: SpectrogramFloat = f32> {
+    pub partials: Vec<Partial<F>>,
+    pub residual: SpectrogramImage<F>,
+    pub settings: SpectrogramSettings,
+}
+
+impl<F: SpectrogramFloat> SinusoidalModel<F> {
+    pub fn analyze(
+        spectrogram: &SpectrogramImage<F>,
+        settings: &SpectrogramSettings,
+        tracking: &PeakTracking,
+        thread_ct: usize,
+    ) -> Self {
+        let partials = spectrogram.track_partials(settings, tracking);
+
+        // Reanalyze the rendered partials frame by frame, exactly where `forward` would have
+        // put each column, and take away their magnitudes.
+        let sines = render_partials(&partials, settings, spectrogram.width, thread_ct);
+        let mut planner = realfft::RealFftPlanner::new();
+        let fft = planner.plan_fft_forward(settings.fft_len());
+        let hop_size = settings.hop_size();
+        let mut residual = SpectrogramImage::new_empty(spectrogram.width, spectrogram.height);
+        for x in 0..spectrogram.width {
+            let seg = &sines[x * hop_size..x * hop_size + settings.window_size];
+            let analyzed =
+                analyze_shifted_real_with_hann_window(&fft, seg, settings.window_pad_amnt);
+            for ((out, bin), sine) in residual
+                .column_mut(x)
+                .iter_mut()
+                .zip(spectrogram.column(x))
+                .zip(analyzed)
+            {
+                let left = bin.norm() - sine.norm() * F::lit(2.0);
+                *out = left.max(F::zero()).into();
+            }
+        }
+
+        Self {
+            partials,
+            residual,
+            settings: *settings,
+        }
+    }
+
+    // Index of the partial closest to fractional bin `bin` at column `x`, if any is within
+    // `max_distance` bins. For picking partials to edit.
+    pub fn partial_near(&self, x: usize, bin: F, max_distance: F) -> Option<usize> {
+        self.partials
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| Some((i, (p.bin_at(x)? - bin).abs())))
+            .filter(|&(_, d)| d <= max_distance)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(i, _)| i)
+    }
+
+    pub fn transpose(&mut self, semitones: F) {
+        for partial in &mut self.partials {
+            partial.transpose(semitones);
+        }
+    }
+
+    // Partials through the oscillator bank, plus the residual with phases from `noise`
+    // scaled by `residual_gain`. Same length and alignment as `inverse::inverse_mt`.
+    pub fn resynthesize(&self, residual_gain: F, noise: &RandomPhases, thread_ct: usize) -> Vec<F> {
+        let mut output = render_partials(
+            &self.partials,
+            &self.settings,
+            self.residual.width,
+            thread_ct,
+        );
+        if residual_gain > F::zero() {
+            let mut residual = self.residual.clone();
+            residual.apply_noise(noise);
+            let residual = inverse_mt(&residual, &self.settings, thread_ct, true);
+            for (o, n) in output.iter_mut().zip(residual) {
+                *o += n * residual_gain;
+            }
+        }
+        output
+    }
+}
@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use realfft::{ComplexToReal, RealToComplex};
+use rustfft::num_complex::Complex;
+
+use crate::{
+    SpectrogramFloat, SpectrogramImage, SpectrogramSettings,
+    forward::analyze_shifted_real_with_hann_window, inverse::undo_to_real_no_changes,
+};
+
+// Chunked counterpart of `forward::analyze_mt`. Samples go in as they arrive and
+// every column whose window has been filled comes back out.
+// The stream is padded with half a window of silence on both ends, so feeding the
+// resulting columns to a `StreamingSynthesizer` reproduces the input delayed by `hop_size`.
+pub struct StreamingAnalyzer<F: SpectrogramFloat = f32> {
+    window_size: usize,
+    pad_amnt: usize,
+    hop_size: usize,
+    fft: Arc<dyn RealToComplex<F>>,
+    pending: Vec<F>,
+}
+
+impl<F: SpectrogramFloat> StreamingAnalyzer<F> {
+    pub fn new(settings: &SpectrogramSettings) -> Self {
+        let window_size = settings.window_size;
+        if window_size % 2 == 1 {
+            panic!()
+        }
+        let hop_size = window_size / 2;
+
+        let mut planner = realfft::RealFftPlanner::new();
+        let fft = planner.plan_fft_forward(window_size + settings.window_pad_amnt);
+
+        Self {
+            window_size,
+            pad_amnt: settings.window_pad_amnt,
+            hop_size,
+            fft,
+            pending: vec![F::zero(); hop_size],
+        }
+    }
+
+    pub fn spectrum_size(&self) -> usize {
+        self.fft.complex_len()
+    }
+
+    pub fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
+    fn drain_complete_columns(&mut self) -> SpectrogramImage<F> {
+        let col_count = if self.pending.len() < self.window_size {
+            0
+        } else {
+            (self.pending.len() - self.window_size) / self.hop_size + 1
+        };
+
+        let mut columns = SpectrogramImage::new_empty(col_count, self.spectrum_size());
+        for x in 0..col_count {
+            let start = x * self.hop_size;
+            let seg = &self.pending[start..start + self.window_size];
+            let analyzed = analyze_shifted_real_with_hann_window(&self.fft, seg, self.pad_amnt);
+            let mags: Vec<_> = analyzed.into_iter().map(|f| f * F::lit(2.0)).collect();
+            columns.set_column(x, &mags);
+        }
+        self.pending.drain(..col_count * self.hop_size);
+
+        columns
+    }
+
+    // Returns the columns completed by this chunk, possibly none.
+    pub fn push_samples(&mut self, samples: &[F]) -> SpectrogramImage<F> {
+        self.pending.extend_from_slice(samples);
+        self.drain_complete_columns()
+    }
+
+    // Pads the tail with silence and returns the remaining columns.
+    pub fn finish(mut self) -> SpectrogramImage<F> {
+        let remaining = self.pending.len();
+        if remaining == 0 {
+            return SpectrogramImage::new_empty(0, self.spectrum_size());
+        }
+        let last_start = remaining.div_ceil(self.hop_size) * self.hop_size - self.hop_size;
+        self.pending
+            .resize(last_start + self.window_size, F::zero());
+        self.drain_complete_columns()
+    }
+}
+
+// Chunked counterpart of `inverse::inverse_mt`. Each pushed column finalizes `hop_size`
+// samples of overlap-added output; concatenating every returned chunk and the result of
+// `finish` gives the same samples `inverse_mt` would.
+pub struct StreamingSynthesizer<F: SpectrogramFloat = f32> {
+    window_size: usize,
+    pad_amnt: usize,
+    hop_size: usize,
+    awful_hack: bool,
+    ifft: Arc<dyn ComplexToReal<F>>,
+    spectrum: Vec<Complex<F>>,
+    overlap: Vec<F>,
+    scale: F,
+}
+
+impl<F: SpectrogramFloat> StreamingSynthesizer<F> {
+    pub fn new(settings: &SpectrogramSettings, awful_hack: bool) -> Self {
+        let window_size = settings.window_size;
+        if window_size % 2 == 1 {
+            panic!()
+        }
+        let hop_size = window_size / 2;
+
+        let mut planner = realfft::RealFftPlanner::new();
+        let ifft = planner.plan_fft_inverse(window_size + settings.window_pad_amnt);
+        let spectrum = ifft.make_input_vec();
+
+        Self {
+            window_size,
+            pad_amnt: settings.window_pad_amnt,
+            hop_size,
+            awful_hack,
+            ifft,
+            spectrum,
+            overlap: vec![F::zero(); window_size],
+            scale: F::lit((window_size + settings.window_pad_amnt) as f64).recip() / F::lit(2.0),
+        }
+    }
+
+    pub fn spectrum_size(&self) -> usize {
+        self.spectrum.len()
+    }
+
+    pub fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
+    fn take_finished_hop(&mut self) -> Vec<F> {
+        let finished: Vec<F> = self.overlap[..self.hop_size]
+            .iter()
+            .map(|s| *s * self.scale)
+            .collect();
+        self.overlap.copy_within(self.hop_size.., 0);
+        let len = self.overlap.len();
+        self.overlap[len - self.hop_size..].fill(F::zero());
+        finished
+    }
+
+    // `column` holds one spectrum, as produced by `SpectrogramImage::get_column`.
+    pub fn push_column(&mut self, column: &[Complex<F>]) -> Vec<F> {
+        let copy_len = column.len().min(self.spectrum.len());
+        self.spectrum[..copy_len].copy_from_slice(&column[..copy_len]);
+        self.spectrum[copy_len..].fill(Complex::ZERO);
+
+        let processed = undo_to_real_no_changes(
+            &self.ifft,
+            &mut self.spectrum,
+            self.pad_amnt,
+            self.awful_hack,
+        );
+        assert_eq!(processed.len(), self.window_size);
+        for (acc, s) in self.overlap.iter_mut().zip(processed) {
+            *acc += s;
+        }
+
+        self.take_finished_hop()
+    }
+
+    pub fn push_columns(&mut self, spectrogram: &SpectrogramImage<F>) -> Vec<F> {
+        let mut column = vec![Complex::ZERO; self.spectrum.len()];
+        let mut samples = Vec::with_capacity(spectrogram.width * self.hop_size);
+        for x in 0..spectrogram.width {
+            spectrogram.get_column(x, &mut column);
+            samples.extend(self.push_column(&column));
+        }
+        samples
+    }
+
+    // Returns the tail still held back for overlap.
+    pub fn finish(mut self) -> Vec<F> {
+        self.take_finished_hop()
+    }
+}
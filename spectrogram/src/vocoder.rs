@@ -0,0 +1,64 @@
+use crate::{
+    SpectrogramFloat, SpectrogramImage,
+    envelope::{EnvelopeExtractor, EnvelopeSmoothing},
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct CrossSynthesisSettings {
+    pub smoothing: EnvelopeSmoothing,
+    // Divide out the carrier's own envelope first, so only its fine structure survives.
+    pub flatten_carrier: bool,
+}
+
+impl Default for CrossSynthesisSettings {
+    fn default() -> Self {
+        Self {
+            smoothing: EnvelopeSmoothing::Bands { count: 24 },
+            flatten_carrier: true,
+        }
+    }
+}
+
+// Imposes the spectral envelope of `modulator` onto `carrier`, frame by frame.
+// Phases come from the carrier, so the result can go straight to `inverse::inverse_mt`.
+pub fn cross_synthesize<F: SpectrogramFloat>(
+    modulator: &SpectrogramImage<F>,
+    carrier: &SpectrogramImage<F>,
+    settings: &CrossSynthesisSettings,
+) -> SpectrogramImage<F> {
+    assert_eq!(
+        modulator.height, carrier.height,
+        "spectrogram heights differ"
+    );
+    let width = modulator.width.min(carrier.width);
+    let height = carrier.height;
+    let extractor = EnvelopeExtractor::new(height, settings.smoothing);
+
+    let modulator_envs: Vec<Vec<F>> = (0..width)
+        .map(|x| extractor.log_envelope(modulator.column(x)))
+        .collect();
+    // Without flattening, the modulator envelope becomes a gain relative to its loudest point.
+    let modulator_peak = modulator_envs
+        .iter()
+        .flatten()
+        .fold(F::neg_infinity(), |a, &b| a.max(b));
+
+    let mut result = SpectrogramImage::new_empty(width, height);
+    for (x, modulator_env) in modulator_envs.iter().enumerate() {
+        let carrier_env = if settings.flatten_carrier {
+            extractor.log_envelope(carrier.column(x))
+        } else {
+            vec![modulator_peak; height]
+        };
+
+        for (y, (out, c)) in result
+            .column_mut(x)
+            .iter_mut()
+            .zip(carrier.column(x))
+            .enumerate()
+        {
+            *out = *c * (modulator_env[y] - carrier_env[y]).exp();
+        }
+    }
+    result
+}
@@ -0,0 +1,221 @@
+use std::sync::Arc;
+
+use rustfft::{Fft, num_complex::Complex};
+
+use crate::{SpectrogramFloat, SpectrogramImage};
+
+// Continuous wavelet transform with Morlet wavelets on a log frequency axis. Each row of
+// the scalogram is one scale, lowest frequency first, so short scales resolve transients a
+// fixed window would smear.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CwtSettings {
+    pub min_freq: f32,
+    pub max_freq: f32,
+    pub voices_per_octave: usize,
+    // Morlet centre frequency in radians per standard deviation of its envelope: higher
+    // trades time resolution for frequency resolution. Below about 5 the wavelet stops
+    // being admissible.
+    pub omega0: f32,
+    // Samples between columns of the displayed image. Rows too wide to be sampled this
+    // coarsely are kept at a divisor of it; see `row_hop`.
+    pub hop_size: usize,
+}
+
+// Rows are sampled at least this many of their frequency standard deviations (f / omega0)
+// apart, so the aliases resynthesis leaves are weighted below -80 dB.
+const ALIAS_SPACING: f32 = 6.1;
+
+impl Default for CwtSettings {
+    fn default() -> Self {
+        Self {
+            min_freq: 40f32,
+            max_freq: 16000f32,
+            voices_per_octave: 24,
+            omega0: 6f32,
+            hop_size: 256,
+        }
+    }
+}
+
+impl CwtSettings {
+    pub fn scale_count(&self) -> usize {
+        ((self.max_freq / self.min_freq).log2() * self.voices_per_octave as f32).floor() as usize
+            + 1
+    }
+
+    // Centre frequency in Hz of row `y`.
+    pub fn scale_frequency(&self, y: usize) -> f32 {
+        self.min_freq * 2f32.powf(y as f32 / self.voices_per_octave as f32)
+    }
+
+    // Samples between the coefficients of row `y`: the largest divisor of `hop_size` that
+    // still samples the row's bandwidth, so it can be interpolated back exactly.
+    pub fn row_hop(&self, y: usize, sample_rate: u32) -> usize {
+        let max = sample_rate as f32 * self.omega0 / (ALIAS_SPACING * self.scale_frequency(y));
+        (1..=self.hop_size)
+            .rev()
+            .find(|&h| self.hop_size.is_multiple_of(h) && h as f32 <= max)
+            .unwrap_or(1)
+    }
+
+    // Length `len` samples are zero-padded to: enough past the end that the longest
+    // wavelet doesn't wrap around into the start, and a whole number of hops.
+    fn padded_len(&self, len: usize, sample_rate: u32) -> usize {
+        let sigma = self.omega0 / (std::f32::consts::TAU * self.min_freq) * sample_rate as f32;
+        (len + (4f32 * sigma).ceil() as usize).next_multiple_of(self.hop_size)
+    }
+
+    // Frequency response of row `y` at bin `k` of a `len`-point FFT, peaking at 1.
+    fn response(&self, y: usize, k: usize, len: usize, sample_rate: u32) -> f64 {
+        let centre = self.scale_frequency(y) as f64;
+        let hz = k as f64 * sample_rate as f64 / len as f64;
+        let d = self.omega0 as f64 * (hz - centre) / centre;
+        (-0.5 * d * d).exp()
+    }
+}
+
+// Splits `0..count` across threads, collecting what each one makes of its share.
+fn per_scale_chunks<T: Send>(
+    count: usize,
+    thread_ct: usize,
+    work: impl Fn(std::ops::Range<usize>) -> T + Sync,
+) -> Vec<T> {
+    let chunk = count.div_ceil(thread_ct.max(1)).max(1);
+    let work = &work;
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..count)
+            .step_by(chunk)
+            .map(|start| scope.spawn(move || work(start..(start + chunk).min(count))))
+            .collect();
+        threads.into_iter().map(|t| t.join().unwrap()).collect()
+    })
+}
+
+// Analytic wavelet coefficients of a signal, magnitudes in the signal's own units: a
+// sinusoid of amplitude 1 at a row's centre frequency reads 1 there.
+#[derive(Clone)]
+pub struct Scalogram<F: SpectrogramFloat = f32> {
+    pub settings: CwtSettings,
+    pub sample_rate: u32,
+    // Length of the analyzed signal.
+    pub len: usize,
+    // Row `y` holds a coefficient every `settings.row_hop(y, sample_rate)` samples, over
+    // the padded signal.
+    pub rows: Vec<Vec<Complex<F>>>,
+}
+
+impl<F: SpectrogramFloat> Scalogram<F> {
+    // The coefficients every `hop_size` samples, lowest row at the bottom, for display:
+    // `IntensityScale::Decibels { reference: 1.0 }` is dBFS.
+    pub fn image(&self) -> SpectrogramImage<F> {
+        let hop = self.settings.hop_size;
+        let width = self.len.div_ceil(hop).max(1);
+        let mut img = SpectrogramImage::new_empty(width, self.rows.len());
+        for (y, row) in self.rows.iter().enumerate() {
+            let step = hop / self.settings.row_hop(y, self.sample_rate);
+            for x in 0..width {
+                *img.mut_get_at(x, y) = row[x * step];
+            }
+        }
+        img
+    }
+}
+
+// Scalogram of `samples`.
+pub fn analyze_cwt<F: SpectrogramFloat>(
+    samples: &[F],
+    settings: &CwtSettings,
+    sample_rate: u32,
+    thread_ct: usize,
+) -> Scalogram<F> {
+    let len = settings.padded_len(samples.len(), sample_rate);
+    let mut planner = realfft::RealFftPlanner::new();
+    let fft = planner.plan_fft_forward(len);
+    let mut input = fft.make_input_vec();
+    input[..samples.len()].copy_from_slice(samples);
+    let mut spectrum = fft.make_output_vec();
+    fft.process(&mut input, &mut spectrum).unwrap();
+
+    let spectrum = &spectrum;
+    let rows = per_scale_chunks(settings.scale_count(), thread_ct, |scales| {
+        let mut planner = rustfft::FftPlanner::new();
+        scales
+            .map(|y| {
+                // Keeping every `hop`th sample folds the spectrum onto `len / hop` bins.
+                // Positive frequencies only, doubled, make the coefficients analytic.
+                let count = len / settings.row_hop(y, sample_rate);
+                let mut row = vec![Complex::<F>::ZERO; count];
+                for k in 1..len.div_ceil(2) {
+                    let weight = 2.0 * settings.response(y, k, len, sample_rate) / len as f64;
+                    row[k % count] += spectrum[k] * F::lit(weight);
+                }
+                let ifft: Arc<dyn Fft<F>> = planner.plan_fft_inverse(count);
+                ifft.process(&mut row);
+                row
+            })
+            .collect::<Vec<_>>()
+    });
+
+    Scalogram {
+        settings: *settings,
+        sample_rate,
+        len: samples.len(),
+        rows: rows.into_iter().flatten().collect(),
+    }
+}
+
+// Inverse of `analyze_cwt`. Each row is sampled above its bandwidth, so repeating its
+// spectrum and weighting it by the row's response interpolates it back to every sample;
+// the rows are then recombined through the dual frame of the wavelets. Exact within
+// `min_freq..max_freq` up to aliasing below -80 dB; nothing outside comes back. Returns
+// as many samples as were analyzed.
+pub fn inverse_cwt<F: SpectrogramFloat>(scalogram: &Scalogram<F>, thread_ct: usize) -> Vec<F> {
+    let settings = &scalogram.settings;
+    let sample_rate = scalogram.sample_rate;
+    let len = settings.padded_len(scalogram.len, sample_rate);
+    let half = len / 2 + 1;
+
+    let partial_sums = per_scale_chunks(scalogram.rows.len(), thread_ct, |scales| {
+        let mut planner = rustfft::FftPlanner::new();
+        let mut sum = vec![Complex::<F>::ZERO; half];
+        let mut weight = vec![0f64; half];
+        for y in scales {
+            let hop = settings.row_hop(y, sample_rate);
+            let mut row = scalogram.rows[y].clone();
+            let fft: Arc<dyn Fft<F>> = planner.plan_fft_forward(row.len());
+            fft.process(&mut row);
+
+            for k in 1..len.div_ceil(2) {
+                let response = settings.response(y, k, len, sample_rate);
+                sum[k] += row[k % row.len()] * F::lit(response * hop as f64);
+                weight[k] += response * response;
+            }
+        }
+        (sum, weight)
+    });
+
+    let mut spectrum = vec![Complex::<F>::ZERO; half];
+    let mut weight = vec![0f64; half];
+    for (s, w) in partial_sums {
+        for k in 0..half {
+            spectrum[k] += s[k];
+            weight[k] += w[k];
+        }
+    }
+    // Each coefficient row's spectrum is twice the signal's times the wavelet's response.
+    let floor = weight.iter().fold(0f64, |a, &b| a.max(b)) * 1e-3;
+    for (c, w) in spectrum.iter_mut().zip(&weight) {
+        *c = if *w > floor {
+            *c * F::lit(0.5 / (w * len as f64))
+        } else {
+            Complex::ZERO
+        };
+    }
+
+    let mut planner = realfft::RealFftPlanner::new();
+    let ifft = planner.plan_fft_inverse(len);
+    let mut output = ifft.make_output_vec();
+    ifft.process(&mut spectrum, &mut output).unwrap();
+    output.truncate(scalogram.len);
+    output
+}
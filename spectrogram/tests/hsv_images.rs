@@ -0,0 +1,81 @@
+use std::f32::consts::{PI, TAU};
+
+use spectrogram::{
+    IntensityScale, SpectrogramImage, SpectrogramPhasePlotSettings, SpectrogramSettings,
+};
+
+const SAMPLE_RATE: f32 = 44100f32;
+
+fn settings() -> SpectrogramSettings {
+    SpectrogramSettings {
+        window_size: 512,
+        window_pad_amnt: 0,
+    }
+}
+
+// A steady tone and a rising chirp, so hues cover the whole circle.
+fn analyzed() -> SpectrogramImage {
+    let samples: Vec<f32> = (0..22050)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE;
+            0.4f32 * (TAU * 1000f32 * t).sin() + 0.3f32 * (TAU * (300f32 + 2000f32 * t) * t).sin()
+        })
+        .collect();
+    spectrogram::forward::analyze_mt(&samples, &settings(), 4).unwrap()
+}
+
+fn phase_error(a: f32, b: f32) -> f32 {
+    (a - b + PI).rem_euclid(TAU) - PI
+}
+
+fn assert_round_trip(lower_seam: f32) {
+    let original = analyzed();
+    let height = original.height;
+    let intensity = original.auto_intensity_settings(
+        [0, height],
+        IntensityScale::dbfs(&settings()),
+        [5f32, 99.9f32],
+    );
+    let phase = SpectrogramPhasePlotSettings {
+        bin_range: [0, height],
+        lower_seam,
+        unwrap_along_time: None,
+    };
+    let bytes = original.create_hsv_bytes(&intensity, &phase);
+
+    let mut restored = original.clone();
+    restored.eliminate_phase();
+    restored.apply_hsv_bytes(&intensity, &phase, &bytes);
+
+    // Brightness survives exactly: it is stored as the largest channel.
+    let brightness = original.create_intensity_bytes(&intensity);
+    assert_eq!(restored.create_intensity_bytes(&intensity), brightness);
+
+    // Hue is held in channels scaled by brightness, so its step is a sixth of the circle
+    // divided by the brightness byte. Two steps cover both channels rounding. DC and
+    // Nyquist are kept real and skipped.
+    let mut checked = 0;
+    for x in 0..original.width {
+        for y in 1..height - 1 {
+            let value = brightness[(height - 1 - y) * original.width + x];
+            if value < 32 {
+                continue;
+            }
+            let tolerance = TAU / 6f32 * 2f32 / value as f32 + 1e-3;
+            let err = phase_error(original.get_at(x, y).arg(), restored.get_at(x, y).arg());
+            assert!(err.abs() <= tolerance, "bin ({x}, {y}) off by {err}");
+            checked += 1;
+        }
+    }
+    assert!(checked > original.width * 4);
+}
+
+#[test]
+fn hsv_round_trips() {
+    assert_round_trip(0f32);
+}
+
+#[test]
+fn hsv_round_trips_with_a_moved_seam() {
+    assert_round_trip(-PI);
+}
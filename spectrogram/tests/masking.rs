@@ -0,0 +1,57 @@
+use std::f32::consts::TAU;
+
+use rustfft::num_complex::Complex;
+use spectrogram::{SpectrogramImage, SpectrogramSettings, additive::SynthesisEngine};
+
+const SAMPLE_RATE: f32 = 44100f32;
+
+fn settings() -> SpectrogramSettings {
+    SpectrogramSettings {
+        window_size: 1024,
+        window_pad_amnt: 0,
+    }
+}
+
+// A low tone and a rising chirp crossing it, so a mask around either cuts into the other.
+fn test_signal() -> Vec<f32> {
+    (0..22050)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE;
+            0.4f32 * (TAU * 440f32 * t).sin() + 0.3f32 * (TAU * (200f32 + 4000f32 * t) * t).sin()
+        })
+        .collect()
+}
+
+// Passes the lower half of the spectrum in the first half of the image, with soft edges.
+fn mask(img: &SpectrogramImage) -> SpectrogramImage {
+    let mut mask = img.uniform_mask(0f32);
+    for x in 0..img.width / 2 {
+        for y in 0..img.height / 2 {
+            *mask.mut_get_at(x, y) = Complex::from(1f32);
+        }
+    }
+    mask.feather_mask(2f32, 4f32);
+    mask
+}
+
+#[test]
+fn kept_and_removed_resynthesize_to_the_whole() {
+    let img = spectrogram::forward::analyze_mt(&test_signal(), &settings(), 4).unwrap();
+    let (kept, removed) = img.split_by_mask(&mask(&img));
+
+    let engine = SynthesisEngine::InverseFft { awful_hack: false };
+    let whole = engine.synthesize(&img, &settings(), 4);
+    let kept = engine.synthesize(&kept, &settings(), 4);
+    let removed = engine.synthesize(&removed, &settings(), 4);
+    assert_eq!(kept.len(), whole.len());
+    assert_eq!(removed.len(), whole.len());
+
+    let peak = whole.iter().fold(0f32, |m, s| m.max(s.abs()));
+    // Both halves carry a real share of the sound.
+    let energy = |s: &[f32]| s.iter().map(|v| v * v).sum::<f32>();
+    let share = energy(&kept) / energy(&whole);
+    assert!(share > 0.1 && share < 0.9, "{share}");
+    for ((w, k), r) in whole.iter().zip(&kept).zip(&removed) {
+        assert!((k + r - w).abs() < 1e-5 * peak, "{k} + {r} vs {w}");
+    }
+}
@@ -0,0 +1,49 @@
+use std::f32::consts::TAU;
+
+use spectrogram::{SpectrogramSettings, additive::SynthesisEngine, multires::MultiResolution};
+
+const SAMPLE_RATE: u32 = 44100;
+
+fn settings() -> Vec<SpectrogramSettings> {
+    [4096, 1024, 256]
+        .into_iter()
+        .map(|window_size| SpectrogramSettings {
+            window_size,
+            window_pad_amnt: 0,
+        })
+        .collect()
+}
+
+// Tones either side of each default crossover and a click, so every band carries something.
+fn test_signal() -> Vec<f32> {
+    (0..22050)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let click = if i == 11025 { 0.5f32 } else { 0f32 };
+            [110f32, 700f32, 3000f32]
+                .iter()
+                .map(|&hz| 0.2f32 * (TAU * hz * t).sin())
+                .sum::<f32>()
+                + click
+        })
+        .collect()
+}
+
+#[test]
+fn bands_sum_to_the_input() {
+    let samples = test_signal();
+    let analysis = MultiResolution::analyze(&samples, &settings(), SAMPLE_RATE, 4);
+    let crossovers = analysis.default_crossovers();
+    let engine = SynthesisEngine::InverseFft { awful_hack: false };
+    let summed = analysis.resynthesize(&crossovers, engine, 4);
+    assert_eq!(summed.len(), samples.len());
+
+    // Away from the fades at either end.
+    let len = samples.len();
+    let (mut error, mut power) = (0f32, 0f32);
+    for i in len / 8..len * 7 / 8 {
+        error += (summed[i] - samples[i]).powi(2);
+        power += samples[i].powi(2);
+    }
+    assert!((error / power).sqrt() < 0.01);
+}
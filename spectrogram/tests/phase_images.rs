@@ -0,0 +1,144 @@
+use std::f32::consts::{PI, TAU};
+
+use spectrogram::{SpectrogramImage, SpectrogramPhasePlotSettings, SpectrogramSettings};
+
+const SAMPLE_RATE: f32 = 44100f32;
+
+fn settings() -> SpectrogramSettings {
+    SpectrogramSettings {
+        window_size: 512,
+        window_pad_amnt: 0,
+    }
+}
+
+// A steady tone, a rising chirp and a little noise, so every kind of phase behaviour shows up.
+fn test_signal() -> Vec<f32> {
+    let mut seed = 1u32;
+    (0..22050)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE;
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            let noise = (seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5f32;
+            0.4f32 * (TAU * 1000f32 * t).sin()
+                + 0.3f32 * (TAU * (300f32 + 2000f32 * t) * t).sin()
+                + 0.01f32 * noise
+        })
+        .collect()
+}
+
+fn analyzed() -> SpectrogramImage {
+    spectrogram::forward::analyze_mt(&test_signal(), &settings(), 4).unwrap()
+}
+
+fn phase_error(a: f32, b: f32) -> f32 {
+    (a - b + PI).rem_euclid(TAU) - PI
+}
+
+// Half a quantization step, plus some slack for float rounding.
+const TOLERANCE: f32 = TAU / 255f32 / 2f32 + 1e-3;
+
+fn assert_round_trip(relative: bool, plot: &SpectrogramPhasePlotSettings) {
+    let original = analyzed();
+    let bytes = if relative {
+        original.create_relative_phase_bytes(plot)
+    } else {
+        original.create_phase_bytes(plot)
+    };
+
+    let mut restored = original.clone();
+    restored.eliminate_phase();
+    restored.apply_phase_bytes(plot, &bytes, relative);
+
+    let [lo, hi] = plot.bin_range;
+    let peak = original.data.iter().fold(0f32, |a, c| a.max(c.norm()));
+    for x in 0..original.width {
+        for y in 0..original.height {
+            let (a, b) = (original.get_at(x, y), restored.get_at(x, y));
+            assert!((a.norm() - b.norm()).abs() <= 1e-3 * peak);
+            if a.norm() < 1e-6 * peak {
+                continue;
+            }
+            if (lo..hi).contains(&y) {
+                let err = phase_error(a.arg(), b.arg());
+                assert!(err.abs() <= TOLERANCE, "bin ({x}, {y}) off by {err}");
+            } else {
+                // Outside the range the phase was eliminated and must stay that way.
+                assert_eq!(b.im, 0f32);
+            }
+        }
+    }
+}
+
+#[test]
+fn absolute_phase_round_trips() {
+    assert_round_trip(
+        false,
+        &SpectrogramPhasePlotSettings {
+            bin_range: [0, 257],
+            lower_seam: 0f32,
+            unwrap_along_time: None,
+        },
+    );
+}
+
+#[test]
+fn absolute_phase_round_trips_over_a_sub_range() {
+    assert_round_trip(
+        false,
+        &SpectrogramPhasePlotSettings {
+            bin_range: [5, 60],
+            lower_seam: -PI,
+            unwrap_along_time: None,
+        },
+    );
+}
+
+#[test]
+fn relative_phase_round_trips_without_drift() {
+    assert_round_trip(
+        true,
+        &SpectrogramPhasePlotSettings {
+            bin_range: [0, 257],
+            lower_seam: 0f32,
+            unwrap_along_time: None,
+        },
+    );
+}
+
+#[test]
+fn unwrapped_relative_phase_round_trips() {
+    assert_round_trip(
+        true,
+        &SpectrogramPhasePlotSettings {
+            bin_range: [3, 120],
+            lower_seam: -PI,
+            unwrap_along_time: Some(settings()),
+        },
+    );
+}
+
+#[test]
+fn unwrapping_centres_a_steady_tone() {
+    // 512-point window at 44.1 kHz: bin 13 is centred on exactly this frequency. Its phase
+    // moves by 13 pi every hop, which unwrapping should take out entirely.
+    let freq = 13f32 * SAMPLE_RATE / 512f32;
+    let samples: Vec<f32> = (0..22050)
+        .map(|i| (TAU * freq * i as f32 / SAMPLE_RATE).sin())
+        .collect();
+    let img = spectrogram::forward::analyze_mt(&samples, &settings(), 4).unwrap();
+    let plot = SpectrogramPhasePlotSettings {
+        bin_range: [13, 14],
+        lower_seam: -PI,
+        unwrap_along_time: Some(settings()),
+    };
+    let bytes = img.create_relative_phase_bytes(&plot);
+
+    // With the seam at -pi, no change at all sits mid-grey. Skip the columns where the tone
+    // fades in and out of the zero padding.
+    for &b in &bytes[2..img.width - 2] {
+        assert!(
+            (b as f32 - 127.5f32).abs() <= 2f32,
+            "unwrapped step reads {b}"
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use std::f32::consts::TAU;
+
+use spectrogram::{
+    SpectrogramImage, SpectrogramSettings,
+    noise::RandomPhases,
+    retrieval::PhaseRetrieval,
+    stream::{StreamingAnalyzer, StreamingSynthesizer},
+};
+
+const SAMPLE_RATE: f32 = 44100f32;
+
+fn settings() -> SpectrogramSettings {
+    SpectrogramSettings {
+        window_size: 512,
+        window_pad_amnt: 0,
+    }
+}
+
+// A steady tone, a rising chirp and a click, so both tonal and transient phases matter.
+fn test_signal() -> Vec<f32> {
+    let mut samples: Vec<f32> = (0..22050)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE;
+            0.4f32 * (TAU * 1000f32 * t).sin() + 0.3f32 * (TAU * (300f32 + 2000f32 * t) * t).sin()
+        })
+        .collect();
+    samples[11025] += 1f32;
+    samples
+}
+
+fn analyze(samples: &[f32]) -> SpectrogramImage {
+    let mut analyzer = StreamingAnalyzer::new(&settings());
+    let head = analyzer.push_samples(samples);
+    let tail = analyzer.finish();
+    let mut img = SpectrogramImage::new_empty(head.width + tail.width, head.height);
+    let mut column = vec![Default::default(); head.height];
+    for x in 0..head.width {
+        head.get_column(x, &mut column);
+        img.set_column(x, &column);
+    }
+    for x in 0..tail.width {
+        tail.get_column(x, &mut column);
+        img.set_column(head.width + x, &column);
+    }
+    img
+}
+
+// How far the magnitudes of what `img` resynthesizes to are from `target`'s, relative to
+// `target`'s: zero for a consistent spectrogram.
+fn spectral_convergence(img: &SpectrogramImage, target: &SpectrogramImage) -> f32 {
+    let mut synthesizer = StreamingSynthesizer::new(&settings(), false);
+    let mut samples = synthesizer.push_columns(img);
+    samples.extend(synthesizer.finish());
+    // Resynthesis comes out a hop late, so column `x + 1` of its analysis lines up with
+    // column `x` of the target.
+    let reanalyzed = analyze(&samples);
+    let (mut error, mut power) = (0f32, 0f32);
+    for x in 0..target.width.min(reanalyzed.width - 1) {
+        for y in 0..target.height {
+            let t = target.get_at(x, y).norm();
+            let r = reanalyzed.get_at(x + 1, y).norm();
+            error += (r - t) * (r - t);
+            power += t * t;
+        }
+    }
+    (error / power).sqrt()
+}
+
+fn retrieved(target: &SpectrogramImage, method: PhaseRetrieval) -> f32 {
+    let mut img = target.clone();
+    img.eliminate_phase();
+    img.retrieve_phases(method, &settings(), &RandomPhases::seeded(7));
+    spectral_convergence(&img, target)
+}
+
+#[test]
+fn true_phases_are_consistent() {
+    let target = analyze(&test_signal());
+    assert!(spectral_convergence(&target, &target) < 0.01);
+}
+
+#[test]
+fn griffin_lim_beats_random_phases() {
+    let target = analyze(&test_signal());
+    let random = retrieved(&target, PhaseRetrieval::Random);
+    let griffin_lim = retrieved(&target, PhaseRetrieval::GriffinLim { iterations: 20 });
+    assert!(
+        griffin_lim < random / 2f32,
+        "{griffin_lim} against {random}"
+    );
+}
+
+#[test]
+fn pghi_beats_random_phases() {
+    let target = analyze(&test_signal());
+    let random = retrieved(&target, PhaseRetrieval::Random);
+    let pghi = retrieved(&target, PhaseRetrieval::Pghi);
+    assert!(pghi < random / 2f32, "{pghi} against {random}");
+}
@@ -0,0 +1,65 @@
+use std::f32::consts::TAU;
+
+use spectrogram::{SpectrogramImage, SpectrogramSettings};
+
+const SAMPLE_RATE: f32 = 44100f32;
+
+fn settings(window_pad_amnt: usize) -> SpectrogramSettings {
+    SpectrogramSettings {
+        window_size: 1024,
+        window_pad_amnt,
+    }
+}
+
+// A tone between bins and a chirp, so padding has something to interpolate.
+fn test_signal() -> Vec<f32> {
+    (0..22050)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE;
+            0.4f32 * (TAU * 1234.5f32 * t).sin() + 0.3f32 * (TAU * (300f32 + 2000f32 * t) * t).sin()
+        })
+        .collect()
+}
+
+fn analyzed(window_pad_amnt: usize) -> SpectrogramImage {
+    spectrogram::forward::analyze_mt(&test_signal(), &settings(window_pad_amnt), 4).unwrap()
+}
+
+#[test]
+fn repadding_matches_analyzing_padded() {
+    let direct = analyzed(1024);
+    let repadded = analyzed(0).repad(&settings(0), &settings(1024)).unwrap();
+    assert_eq!(repadded.width, direct.width);
+    assert_eq!(repadded.height, direct.height);
+
+    let peak = direct.data.iter().fold(0f32, |m, c| m.max(c.norm()));
+    for (a, b) in repadded.data.iter().zip(&direct.data) {
+        assert!((a - b).norm() < 1e-4 * peak, "{a} vs {b}");
+    }
+}
+
+#[test]
+fn repadding_back_restores_the_original() {
+    let original = analyzed(0);
+    let restored = original
+        .repad(&settings(0), &settings(1024))
+        .and_then(|padded| padded.repad(&settings(1024), &settings(0)))
+        .unwrap();
+
+    let peak = original.data.iter().fold(0f32, |m, c| m.max(c.norm()));
+    for (a, b) in restored.data.iter().zip(&original.data) {
+        assert!((a - b).norm() < 1e-4 * peak, "{a} vs {b}");
+    }
+}
+
+#[test]
+fn repad_refuses_mismatched_settings() {
+    let img = analyzed(0);
+    let other_window = SpectrogramSettings {
+        window_size: 512,
+        window_pad_amnt: 0,
+    };
+    assert!(img.repad(&settings(0), &other_window).is_none());
+    // The image is not 1024-padded to begin with.
+    assert!(img.repad(&settings(1024), &settings(0)).is_none());
+}
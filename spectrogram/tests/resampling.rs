@@ -0,0 +1,60 @@
+use std::f64::consts::TAU;
+
+use spectrogram::resample::resample;
+
+fn sine(hz: f64, sample_rate: u32, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| (0.5 * (TAU * hz * i as f64 / sample_rate as f64).sin()) as f32)
+        .collect()
+}
+
+// Amplitude of the `hz` component of `samples`, over a whole number of cycles taken from
+// the middle, away from where the filter runs off the ends.
+fn amplitude(samples: &[f32], hz: f64, sample_rate: u32) -> f64 {
+    let period = sample_rate as f64 / hz;
+    let n = ((samples.len() as f64 / 2.0 / period).floor() * period).round() as usize;
+    let start = (samples.len() - n) / 2;
+    let (mut re, mut im) = (0f64, 0f64);
+    for (i, &s) in samples[start..start + n].iter().enumerate() {
+        let phase = TAU * hz * i as f64 / sample_rate as f64;
+        re += s as f64 * phase.cos();
+        im += s as f64 * phase.sin();
+    }
+    2.0 * (re * re + im * im).sqrt() / n as f64
+}
+
+fn rms_middle(samples: &[f32]) -> f64 {
+    let middle = &samples[samples.len() / 4..samples.len() * 3 / 4];
+    (middle.iter().map(|&s| (s as f64).powi(2)).sum::<f64>() / middle.len() as f64).sqrt()
+}
+
+#[test]
+fn output_covers_the_same_time() {
+    assert_eq!(resample(&vec![0f32; 44100], 44100, 48000).len(), 48000);
+    assert_eq!(resample(&vec![0f32; 48000], 48000, 8000).len(), 8000);
+    // A partial output period still gets a sample.
+    assert_eq!(resample(&vec![0f32; 100], 44100, 48000).len(), 109);
+    assert_eq!(resample(&vec![0f32; 100], 48000, 8000).len(), 17);
+}
+
+#[test]
+fn passband_keeps_its_level() {
+    for (from, to) in [(44100, 48000), (48000, 8000)] {
+        let out = resample(&sine(1000.0, from, from as usize), from, to);
+        let db = 20.0 * (amplitude(&out, 1000.0, to) / 0.5).log10();
+        assert!(db.abs() < 0.1, "{from} -> {to}: {db} dB");
+    }
+}
+
+#[test]
+fn downsampling_removes_tones_above_the_new_nyquist() {
+    let out = resample(&sine(6000.0, 48000, 48000), 48000, 8000);
+    let db = 20.0 * (rms_middle(&out) / (0.5 / 2f64.sqrt())).log10();
+    assert!(db < -60.0, "{db} dB");
+}
+
+#[test]
+fn same_rate_is_the_identity() {
+    let samples = sine(1000.0, 44100, 1000);
+    assert_eq!(resample(&samples, 44100, 44100), samples);
+}
@@ -0,0 +1,54 @@
+use rustfft::num_complex::Complex;
+use spectrogram::{
+    SpectrogramImage,
+    noise::{NoiseDistribution, RandomPhases},
+};
+
+const DISTRIBUTIONS: [NoiseDistribution; 3] = [
+    NoiseDistribution::UniformPhase,
+    NoiseDistribution::GaussianComplex,
+    NoiseDistribution::Pink,
+];
+
+// Magnitudes rising with frequency, so every bin has something to scatter.
+fn ramp() -> SpectrogramImage {
+    let mut img = SpectrogramImage::new_empty(16, 33);
+    for (i, c) in img.data.iter_mut().enumerate() {
+        *c = Complex::from(1f32 + (i % 33) as f32);
+    }
+    img
+}
+
+fn noisy(distribution: NoiseDistribution, seed: u64) -> Vec<u32> {
+    let mut img = ramp();
+    img.apply_noise(&RandomPhases {
+        distribution,
+        ..RandomPhases::seeded(seed)
+    });
+    img.data
+        .iter()
+        .flat_map(|c| [c.re.to_bits(), c.im.to_bits()])
+        .collect()
+}
+
+#[test]
+fn same_seed_gives_identical_noise() {
+    for distribution in DISTRIBUTIONS {
+        assert_eq!(
+            noisy(distribution, 3),
+            noisy(distribution, 3),
+            "{distribution:?}"
+        );
+    }
+}
+
+#[test]
+fn different_seeds_give_different_noise() {
+    for distribution in DISTRIBUTIONS {
+        assert_ne!(
+            noisy(distribution, 3),
+            noisy(distribution, 4),
+            "{distribution:?}"
+        );
+    }
+}
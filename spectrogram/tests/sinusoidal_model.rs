@@ -0,0 +1,86 @@
+use std::f32::consts::TAU;
+
+use spectrogram::{
+    SpectrogramImage, SpectrogramSettings,
+    noise::RandomPhases,
+    sinusoidal::{PeakTracking, SinusoidalModel},
+};
+
+const SAMPLE_RATE: f32 = 44100f32;
+
+fn settings() -> SpectrogramSettings {
+    SpectrogramSettings {
+        window_size: 1024,
+        window_pad_amnt: 0,
+    }
+}
+
+// Two steady tones centred on these bins.
+const BINS: [f32; 2] = [20f32, 47f32];
+const AMPLITUDE: f32 = 0.3f32;
+
+fn two_tones() -> Vec<f32> {
+    (0..44100)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE;
+            BINS.iter()
+                .map(|&bin| AMPLITUDE * (TAU * bin * SAMPLE_RATE / 1024f32 * t).sin())
+                .sum()
+        })
+        .collect()
+}
+
+fn model() -> (SpectrogramImage, SinusoidalModel) {
+    let img = spectrogram::forward::analyze_mt(&two_tones(), &settings(), 4).unwrap();
+    let model = SinusoidalModel::analyze(&img, &settings(), &PeakTracking::default(), 4);
+    (img, model)
+}
+
+#[test]
+fn two_tones_make_two_partials() {
+    let (img, model) = model();
+    // Anything else is leakage down near the -80 dB floor.
+    let mut loud: Vec<_> = model
+        .partials
+        .iter()
+        .filter(|p| p.amplitudes.iter().any(|&a| a > 0.01))
+        .collect();
+    loud.sort_by(|a, b| a.bins[0].partial_cmp(&b.bins[0]).unwrap());
+    assert_eq!(loud.len(), 2);
+    for (partial, expected) in loud.into_iter().zip(BINS) {
+        assert!(partial.bins.len() > img.width * 9 / 10);
+        // The onset and release columns see the tone cut off mid-window.
+        let steady = &partial.bins[1..partial.bins.len() - 1];
+        for &bin in steady {
+            assert!((bin - expected).abs() < 0.05, "{bin} for {expected}");
+        }
+        let peak = partial.amplitudes.iter().fold(0f32, |a, &b| a.max(b));
+        assert!((peak - AMPLITUDE).abs() < 0.01 * AMPLITUDE);
+    }
+}
+
+#[test]
+fn partials_resynthesize_the_tones() {
+    let (img, model) = model();
+    let original = spectrogram::inverse::inverse_mt(&img, &settings(), 4, false);
+    let resynthesized = model.resynthesize(0f32, &RandomPhases::seeded(1), 4);
+    assert_eq!(resynthesized.len(), original.len());
+    // Away from the fades at either end.
+    let len = original.len();
+    let (mut error, mut power) = (0f32, 0f32);
+    for i in len / 8..len * 7 / 8 {
+        error += (resynthesized[i] - original[i]).powi(2);
+        power += original[i].powi(2);
+    }
+    assert!((error / power).sqrt() < 0.05);
+}
+
+#[test]
+fn partial_near_picks_the_closest_partial() {
+    let (_, model) = model();
+    let low = model.partial_near(40, 21f32, 3f32).unwrap();
+    let high = model.partial_near(40, 45.5f32, 3f32).unwrap();
+    assert!((model.partials[low].bin_at(40).unwrap() - BINS[0]).abs() < 0.05);
+    assert!((model.partials[high].bin_at(40).unwrap() - BINS[1]).abs() < 0.05);
+    assert_eq!(model.partial_near(40, 33f32, 3f32), None);
+}
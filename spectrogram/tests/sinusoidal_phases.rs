@@ -0,0 +1,110 @@
+use rustfft::num_complex::Complex;
+use spectrogram::{SinusoidFrequency, SpectrogramImage, SpectrogramSettings};
+
+fn settings() -> SpectrogramSettings {
+    SpectrogramSettings {
+        window_size: 1024,
+        window_pad_amnt: 0,
+    }
+}
+
+const WIDTH: usize = 60;
+const LINE_BIN: usize = 37;
+
+// A horizontal line across every column, `profile` giving the magnitudes of the bins from
+// `LINE_BIN` upward.
+fn painted_line(profile: &[f32]) -> SpectrogramImage {
+    let settings = settings();
+    let mut img = SpectrogramImage::new_empty(WIDTH, settings.fft_len() / 2 + 1);
+    for x in 0..WIDTH {
+        for (i, &mag) in profile.iter().enumerate() {
+            *img.mut_get_at(x, LINE_BIN + i) = Complex::from(mag * settings.full_scale_magnitude());
+        }
+    }
+    img
+}
+
+fn resynthesize(img: &SpectrogramImage) -> Vec<f32> {
+    spectrogram::inverse::inverse_mt(img, &settings(), 4, false)
+}
+
+// Peak level of every stretch one carrier period long, skipping the fades at either end
+// and the zero padding `forward` adds. Sampling alone makes a steady tone wobble by well
+// under a percent.
+fn envelope(samples: &[f32], frequency: f32) -> Vec<f32> {
+    let hop = settings().hop_size();
+    let period = (1f32 / frequency).ceil() as usize;
+    samples[4 * hop..samples.len() - 4 * hop]
+        .chunks_exact(period)
+        .map(|block| block.iter().fold(0f32, |a, s| a.max(s.abs())))
+        .collect()
+}
+
+// Largest deviation of the envelope from its mean, as a fraction of the mean.
+fn level_ripple(samples: &[f32], frequency: f32) -> f32 {
+    let levels = envelope(samples, frequency);
+    let mean = levels.iter().sum::<f32>() / levels.len() as f32;
+    levels
+        .iter()
+        .map(|l| (l - mean).abs() / mean)
+        .fold(0f32, f32::max)
+}
+
+fn zero_crossings_per_sample(samples: &[f32]) -> f32 {
+    let hop = settings().hop_size();
+    let steady = &samples[4 * hop..samples.len() - 4 * hop];
+    let rising = steady
+        .windows(2)
+        .filter(|w| w[0] < 0f32 && w[1] >= 0f32)
+        .count();
+    rising as f32 / steady.len() as f32
+}
+
+#[test]
+fn single_bin_line_is_a_steady_tone() {
+    let mut img = painted_line(&[1f32]);
+    img.apply_sinusoidal_phases(&settings(), SinusoidFrequency::BinCentre);
+    let samples = resynthesize(&img);
+
+    let expected = LINE_BIN as f32 / settings().fft_len() as f32;
+    assert!(level_ripple(&samples, expected) < 0.01);
+    assert!((zero_crossings_per_sample(&samples) - expected).abs() < 0.001);
+}
+
+#[test]
+fn wide_line_is_a_steady_tone_with_instantaneous_frequency() {
+    let mut img = painted_line(&[0.5f32, 1f32, 0.5f32]);
+    img.apply_sinusoidal_phases(&settings(), SinusoidFrequency::Instantaneous);
+    let samples = resynthesize(&img);
+
+    // The peak of the line is its middle bin.
+    let expected = (LINE_BIN + 1) as f32 / settings().fft_len() as f32;
+    assert!(level_ripple(&samples, expected) < 0.01);
+    assert!((zero_crossings_per_sample(&samples) - expected).abs() < 0.001);
+}
+
+#[test]
+fn wide_line_beats_with_bin_centres() {
+    let mut img = painted_line(&[0.5f32, 1f32, 0.5f32]);
+    img.apply_sinusoidal_phases(&settings(), SinusoidFrequency::BinCentre);
+
+    let expected = (LINE_BIN + 1) as f32 / settings().fft_len() as f32;
+    assert!(level_ripple(&resynthesize(&img), expected) > 0.1);
+}
+
+#[test]
+fn analyzed_tone_resynthesizes_steadily_without_its_phases() {
+    let settings = settings();
+    // Between bin centres, where the leakage over several bins is widest.
+    let frequency = (LINE_BIN as f32 + 0.4f32) / settings.fft_len() as f32;
+    let tone: Vec<f32> = (0..WIDTH * settings.hop_size())
+        .map(|i| 0.5f32 * (std::f32::consts::TAU * frequency * i as f32).sin())
+        .collect();
+    let mut img = spectrogram::forward::analyze_mt(&tone, &settings, 4).unwrap();
+    img.eliminate_phase();
+    img.apply_sinusoidal_phases(&settings, SinusoidFrequency::Instantaneous);
+    let samples = resynthesize(&img);
+
+    assert!(level_ripple(&samples, frequency) < 0.01);
+    assert!((zero_crossings_per_sample(&samples) - frequency).abs() < 0.001);
+}
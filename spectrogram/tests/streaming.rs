@@ -0,0 +1,98 @@
+use std::f32::consts::TAU;
+
+use rustfft::num_complex::Complex;
+use spectrogram::{
+    SpectrogramImage, SpectrogramSettings,
+    stream::{StreamingAnalyzer, StreamingSynthesizer},
+};
+
+const SAMPLE_RATE: f32 = 44100f32;
+
+// A steady tone over a rising chirp.
+fn test_signal() -> Vec<f32> {
+    (0..22050)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE;
+            0.4f32 * (TAU * 1000f32 * t).sin() + 0.3f32 * (TAU * (300f32 + 2000f32 * t) * t).sin()
+        })
+        .collect()
+}
+
+// Feeds `spectrogram` to a synthesizer a column, then uneven runs of columns, at a time.
+fn stream_synthesis(
+    spectrogram: &SpectrogramImage,
+    settings: &SpectrogramSettings,
+    awful_hack: bool,
+) -> Vec<f32> {
+    let mut synthesizer = StreamingSynthesizer::new(settings, awful_hack);
+    let mut column = vec![Complex::ZERO; spectrogram.height];
+    let mut samples = vec![];
+    let mut x = 0;
+    let mut run = 1;
+    while x < spectrogram.width {
+        let end = (x + run).min(spectrogram.width);
+        let mut chunk = SpectrogramImage::new_empty(end - x, spectrogram.height);
+        for (i, cx) in (x..end).enumerate() {
+            spectrogram.get_column(cx, &mut column);
+            chunk.set_column(i, &column);
+        }
+        samples.extend(synthesizer.push_columns(&chunk));
+        x = end;
+        run = run * 2 + 1;
+    }
+    samples.extend(synthesizer.finish());
+    samples
+}
+
+fn assert_matches_inverse_mt(settings: SpectrogramSettings, awful_hack: bool) {
+    let spectrogram = spectrogram::forward::analyze_mt(&test_signal(), &settings, 4).unwrap();
+    let expected = spectrogram::inverse::inverse_mt(&spectrogram, &settings, 4, awful_hack);
+    let streamed = stream_synthesis(&spectrogram, &settings, awful_hack);
+    assert_eq!(streamed.len(), expected.len());
+    for (i, (s, e)) in streamed.iter().zip(&expected).enumerate() {
+        assert!((s - e).abs() < 1e-5, "sample {}: {} != {}", i, s, e);
+    }
+}
+
+#[test]
+fn streaming_synthesis_matches_inverse_mt() {
+    let settings = SpectrogramSettings {
+        window_size: 512,
+        window_pad_amnt: 0,
+    };
+    assert_matches_inverse_mt(settings, false);
+    assert_matches_inverse_mt(settings, true);
+}
+
+#[test]
+fn streaming_synthesis_matches_inverse_mt_with_padding() {
+    let settings = SpectrogramSettings {
+        window_size: 512,
+        window_pad_amnt: 512,
+    };
+    assert_matches_inverse_mt(settings, false);
+}
+
+// Columns from the streaming analyzer resynthesize to the input, a hop late, up to the
+// ripple of the overlapping Hann windows, which sum to one only to about pi / window_size.
+#[test]
+fn streamed_analysis_resynthesizes_the_input() {
+    let settings = SpectrogramSettings {
+        window_size: 512,
+        window_pad_amnt: 0,
+    };
+    let input = test_signal();
+    let mut analyzer = StreamingAnalyzer::new(&settings);
+    let mut synthesizer = StreamingSynthesizer::new(&settings, false);
+    let mut output = vec![];
+    for chunk in input.chunks(1000) {
+        output.extend(synthesizer.push_columns(&analyzer.push_samples(chunk)));
+    }
+    output.extend(synthesizer.push_columns(&analyzer.finish()));
+    output.extend(synthesizer.finish());
+
+    let hop = settings.hop_size();
+    for (i, s) in input.iter().enumerate() {
+        assert!((output[i + hop] - s).abs() < 1e-2, "sample {}", i);
+    }
+}
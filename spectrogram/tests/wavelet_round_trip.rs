@@ -0,0 +1,86 @@
+use rustfft::num_complex::Complex;
+use spectrogram::wavelet::{CwtSettings, analyze_cwt, inverse_cwt};
+
+const SAMPLE_RATE: u32 = 44100;
+
+// `samples` with everything outside `low..high` Hz removed.
+fn band_limited(samples: &[f32], low: f32, high: f32) -> Vec<f32> {
+    let mut planner = realfft::RealFftPlanner::new();
+    let fft = planner.plan_fft_forward(samples.len());
+    let ifft = planner.plan_fft_inverse(samples.len());
+    let mut input = samples.to_vec();
+    let mut spectrum = fft.make_output_vec();
+    fft.process(&mut input, &mut spectrum).unwrap();
+    for (k, c) in spectrum.iter_mut().enumerate() {
+        let hz = k as f32 * SAMPLE_RATE as f32 / samples.len() as f32;
+        if hz < low || hz > high {
+            *c = Complex::ZERO;
+        }
+    }
+    let mut output = ifft.make_output_vec();
+    ifft.process(&mut spectrum, &mut output).unwrap();
+    output.iter().map(|s| s / samples.len() as f32).collect()
+}
+
+// Clicks every 0.1 s.
+fn click_train() -> Vec<f32> {
+    let mut samples = vec![0f32; SAMPLE_RATE as usize / 2];
+    for i in (1000..samples.len()).step_by(SAMPLE_RATE as usize / 10) {
+        samples[i] = 1f32;
+    }
+    band_limited(&samples, 100f32, 8000f32)
+}
+
+fn noise() -> Vec<f32> {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let samples: Vec<f32> = (0..SAMPLE_RATE / 2)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 40) as f32 / (1u64 << 23) as f32 - 1f32
+        })
+        .collect();
+    band_limited(&samples, 100f32, 8000f32)
+}
+
+fn sine() -> Vec<f32> {
+    (0..SAMPLE_RATE / 2)
+        .map(|n| (std::f32::consts::TAU * 1000f32 * n as f32 / SAMPLE_RATE as f32).sin())
+        .collect()
+}
+
+// RMS of the difference over RMS of `original`.
+fn relative_error(original: &[f32], settings: &CwtSettings) -> f32 {
+    let scalogram = analyze_cwt(original, settings, SAMPLE_RATE, 4);
+    let resynthesized = inverse_cwt(&scalogram, 4);
+    assert_eq!(resynthesized.len(), original.len());
+    let power = |s: &mut dyn Iterator<Item = f32>| s.map(|v| v * v).sum::<f32>();
+    let error = power(&mut original.iter().zip(&resynthesized).map(|(a, b)| a - b));
+    (error / power(&mut original.iter().copied())).sqrt()
+}
+
+#[test]
+fn click_train_round_trips() {
+    assert!(relative_error(&click_train(), &CwtSettings::default()) < 0.01);
+}
+
+#[test]
+fn noise_round_trips() {
+    assert!(relative_error(&noise(), &CwtSettings::default()) < 0.01);
+}
+
+#[test]
+fn sine_round_trips() {
+    assert!(relative_error(&sine(), &CwtSettings::default()) < 0.01);
+}
+
+// Where the signal sits between columns of the display image makes no difference.
+#[test]
+fn coarse_display_hop_round_trips() {
+    let settings = CwtSettings {
+        hop_size: 1024,
+        ..Default::default()
+    };
+    assert!(relative_error(&click_train(), &settings) < 0.01);
+}